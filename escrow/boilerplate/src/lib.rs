@@ -101,8 +101,65 @@ blueprint! {
             to_be_paid_by_party_1: ResourceSpecifier,
             to_be_paid_by_party_2: ResourceSpecifier,
         ) -> (ComponentAddress, Bucket) {
-            // TODO: Complete this function yourself.
-            todo!()
+            // Performing the checks on the resource specifiers to ensure that they are valid and
+            // that they specify two different things to be exchanged.
+            assert!(
+                to_be_paid_by_party_1.validate().is_ok(),
+                "[Instantiation]: Invalid resource specifier for the first party."
+            );
+            assert!(
+                to_be_paid_by_party_2.validate().is_ok(),
+                "[Instantiation]: Invalid resource specifier for the second party."
+            );
+            assert_ne!(
+                to_be_paid_by_party_1, to_be_paid_by_party_2,
+                "[Instantiation]: The two parties can not be paying the exact same thing."
+            );
+
+            // Creating the obligations of the two parties. What one party pays is what the other
+            // party gets.
+            let party_1_obligation: EscrowObligation = EscrowObligation {
+                amount_to_pay: to_be_paid_by_party_1.clone(),
+                amount_to_get: to_be_paid_by_party_2.clone(),
+            };
+            let party_2_obligation: EscrowObligation = EscrowObligation {
+                amount_to_pay: to_be_paid_by_party_2.clone(),
+                amount_to_get: to_be_paid_by_party_1.clone(),
+            };
+
+            // Creating the obligation NFTs of the two parties, with the ids 1 and 2 respectively.
+            let escrow_obligations: Bucket = ResourceBuilder::new_non_fungible()
+                .metadata("name", "Escrow Obligation")
+                .metadata(
+                    "description",
+                    "This NFT defines the obligation of a party to an escrow and what it is owed",
+                )
+                .initial_supply([
+                    (NonFungibleId::from_u32(1), party_1_obligation),
+                    (NonFungibleId::from_u32(2), party_2_obligation),
+                ]);
+
+            // Creating the vaults where the funds of each of the two parties will be deposited.
+            let mut vaults: BTreeMap<ResourceSpecifier, Vault> = BTreeMap::new();
+            vaults.insert(
+                to_be_paid_by_party_1.clone(),
+                Vault::new(to_be_paid_by_party_1.resource_address()),
+            );
+            vaults.insert(
+                to_be_paid_by_party_2.clone(),
+                Vault::new(to_be_paid_by_party_2.resource_address()),
+            );
+
+            // Instantiating the escrow component and globalizing it
+            let escrow: ComponentAddress = Self {
+                vaults,
+                obligation_non_fungible_resource: escrow_obligations.resource_address(),
+                is_escrow_fulfilled: false,
+            }
+            .instantiate()
+            .globalize();
+
+            (escrow, escrow_obligations)
         }
 
         /// Deposits funds into the escrow by one of the parties.
@@ -133,6 +190,9 @@ blueprint! {
         /// error.
         /// 3. To avoid any user errors, it checks that this obligation has not yet been fulfilled.
         /// As in, it makes sure that this party did not already pay its obligation.
+        /// 4. Checks that the escrow has not yet been fulfilled. Once both parties have deposited,
+        /// a party which already withdrew its funds leaves an empty vault behind, and depositing
+        /// into it again would lock these funds in the escrow for good.
         ///
         /// # Note
         ///
@@ -151,8 +211,43 @@ blueprint! {
         ///
         /// [`Bucket`] - A bucket containing any excess tokens that were sent to this method.
         pub fn deposit(&mut self, obligation_badge: Proof, mut funds: Bucket) -> Bucket {
-            // TODO: Complete this function yourself.
-            todo!()
+            // Performing the checks on the escrow, the obligation badge and the funds.
+            assert!(
+                !self.is_escrow_fulfilled(),
+                "[Deposit]: The escrow has already been fulfilled."
+            );
+            assert_eq!(
+                obligation_badge.resource_address(),
+                self.obligation_non_fungible_resource,
+                "[Deposit]: Invalid obligation badge."
+            );
+            let obligation: EscrowObligation = obligation_badge
+                .non_fungible::<EscrowObligation>()
+                .data();
+            assert_eq!(
+                funds.resource_address(),
+                obligation.amount_to_pay.resource_address(),
+                "[Deposit]: The funds do not match the resource which this party needs to pay."
+            );
+
+            // Since partial deposits are not supported, the vault of this obligation is only empty
+            // for as long as the obligation has not been fulfilled.
+            let vault: &mut Vault = self.vaults.get_mut(&obligation.amount_to_pay).unwrap();
+            assert!(
+                vault.is_empty(),
+                "[Deposit]: This party has already fulfilled its obligation."
+            );
+
+            // Taking the amount to pay from the funds and depositing it into the vault.
+            match obligation.amount_to_pay {
+                ResourceSpecifier::Fungible { amount, .. } => vault.put(funds.take(amount)),
+                ResourceSpecifier::NonFungible {
+                    non_fungible_ids, ..
+                } => vault.put(funds.take_non_fungibles(&non_fungible_ids)),
+            }
+
+            // Returning any excess funds back to the caller.
+            funds
         }
 
         /// Withdraws funds from the escrow after both parties have deposited their funds.
@@ -184,8 +279,25 @@ blueprint! {
         ///
         /// [`Bucket`] - A bucket containing the owed tokens.
         pub fn withdraw(&mut self, obligation_badge: Proof) -> Bucket {
-            // TODO: Complete this function yourself.
-            todo!()
+            // Performing the checks on the escrow and the obligation badge.
+            assert!(
+                self.is_escrow_fulfilled(),
+                "[Withdraw]: The escrow has not been fulfilled yet."
+            );
+            assert_eq!(
+                obligation_badge.resource_address(),
+                self.obligation_non_fungible_resource,
+                "[Withdraw]: Invalid obligation badge."
+            );
+
+            // Taking all of the funds owed to this party out of their vault.
+            let obligation: EscrowObligation = obligation_badge
+                .non_fungible::<EscrowObligation>()
+                .data();
+            self.vaults
+                .get_mut(&obligation.amount_to_get)
+                .unwrap()
+                .take_all()
         }
 
         /// Checks if the escrow is fulfilled or not and returns a boolean output.
@@ -200,8 +312,9 @@ blueprint! {
         /// This method makes no assumption on whether the deposit methods reject extra tokens sent
         /// or not. With this in mind, all of the operations do not check for strict equality but
         /// check that the amount required **OR MORE** is provided. For the case of NFTs and their
-        /// [`NonFungibleId`]s, then this function checks that the set of [`NonFungibleId`]s in the
-        /// [`Vault`] is a subset of the [`NonFungibleId`]s required by the [`ResourceSpecifier`].
+        /// [`NonFungibleId`]s, then this function checks that the set of [`NonFungibleId`]s
+        /// required by the [`ResourceSpecifier`] is a subset of the [`NonFungibleId`]s in the
+        /// [`Vault`].
         ///
         /// # Returns
         ///
@@ -233,10 +346,7 @@ blueprint! {
                                 non_fungible_ids,
                             } => {
                                 vault.resource_address() == *resource_address
-                                    && vault
-                                        .non_fungible_ids()
-                                        .iter()
-                                        .all(|x| non_fungible_ids.contains(x))
+                                    && non_fungible_ids.is_subset(&vault.non_fungible_ids())
                             }
                         }
                     })
@@ -0,0 +1,503 @@
+use escrow::ResourceSpecifier;
+use radix_engine::ledger::TypedInMemorySubstateStore;
+use radix_engine::transaction::TransactionReceipt;
+use scrypto::core::NetworkDefinition;
+use scrypto::prelude::*;
+use scrypto_unit::*;
+use transaction::builder::ManifestBuilder;
+use transaction::model::TransactionManifest;
+
+/// A party to the escrow, made up of its public key and account.
+struct Party {
+    public_key: EcdsaPublicKey,
+    account: ComponentAddress,
+}
+
+/// Creates the accounts of the two parties to the escrow.
+fn new_parties(test_runner: &mut TestRunner<TypedInMemorySubstateStore>) -> (Party, Party) {
+    let (public_key, _private_key, account) = test_runner.new_account();
+    let party_1 = Party { public_key, account };
+    let (public_key, _private_key, account) = test_runner.new_account();
+    let party_2 = Party { public_key, account };
+    (party_1, party_2)
+}
+
+/// Executes the given manifest signed by the given party.
+fn execute(
+    test_runner: &mut TestRunner<TypedInMemorySubstateStore>,
+    party: &Party,
+    manifest: TransactionManifest,
+) -> TransactionReceipt {
+    let receipt = test_runner.execute_manifest(
+        manifest,
+        vec![NonFungibleAddress::from_public_key(&party.public_key)],
+    );
+    println!("{:?}\n", receipt);
+    receipt
+}
+
+/// Instantiates an escrow by the first party with the given obligations, and sends the obligation
+/// NFT with id 2 to the second party. Returns the address of the escrow component and of the
+/// obligation NFTs.
+fn instantiate_escrow(
+    test_runner: &mut TestRunner<TypedInMemorySubstateStore>,
+    package_address: PackageAddress,
+    party_1: &Party,
+    party_2: &Party,
+    to_be_paid_by_party_1: ResourceSpecifier,
+    to_be_paid_by_party_2: ResourceSpecifier,
+) -> (ComponentAddress, ResourceAddress) {
+    let manifest = ManifestBuilder::new(&NetworkDefinition::simulator())
+        .lock_fee(10.into(), SYS_FAUCET_COMPONENT)
+        .call_function(
+            package_address,
+            "Escrow",
+            "instantiate_escrow",
+            args!(to_be_paid_by_party_1, to_be_paid_by_party_2),
+        )
+        .call_method(
+            party_1.account,
+            "deposit_batch",
+            args!(Expression::entire_worktop()),
+        )
+        .build();
+    let receipt = execute(test_runner, party_1, manifest);
+    receipt.expect_commit_success();
+    let escrow = receipt.expect_commit().entity_changes.new_component_addresses[0];
+    let obligation_nft = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    let manifest = ManifestBuilder::new(&NetworkDefinition::simulator())
+        .lock_fee(10.into(), SYS_FAUCET_COMPONENT)
+        .withdraw_from_account_by_ids(
+            &BTreeSet::from([NonFungibleId::from_u32(2)]),
+            obligation_nft,
+            party_1.account,
+        )
+        .call_method(
+            party_2.account,
+            "deposit_batch",
+            args!(Expression::entire_worktop()),
+        )
+        .build();
+    execute(test_runner, party_1, manifest).expect_commit_success();
+
+    (escrow, obligation_nft)
+}
+
+/// Deposits the given funds of a party into the escrow with its obligation NFT, which is taken
+/// from `badge_resource`, returning any excess funds to the party.
+fn deposit(
+    test_runner: &mut TestRunner<TypedInMemorySubstateStore>,
+    escrow: ComponentAddress,
+    party: &Party,
+    badge_resource: ResourceAddress,
+    funds: ResourceSpecifier,
+) -> TransactionReceipt {
+    let mut manifest_builder = ManifestBuilder::new(&NetworkDefinition::simulator());
+    manifest_builder
+        .lock_fee(10.into(), SYS_FAUCET_COMPONENT)
+        .create_proof_from_account(badge_resource, party.account);
+    match &funds {
+        ResourceSpecifier::Fungible {
+            resource_address,
+            amount,
+        } => manifest_builder.withdraw_from_account_by_amount(
+            *amount,
+            *resource_address,
+            party.account,
+        ),
+        ResourceSpecifier::NonFungible {
+            resource_address,
+            non_fungible_ids,
+        } => manifest_builder.withdraw_from_account_by_ids(
+            non_fungible_ids,
+            *resource_address,
+            party.account,
+        ),
+    };
+    let manifest = manifest_builder
+        .pop_from_auth_zone(|builder, proof_id| {
+            builder.take_from_worktop(funds.resource_address(), |builder, bucket_id| {
+                builder.call_method(escrow, "deposit", args!(Proof(proof_id), Bucket(bucket_id)))
+            })
+        })
+        .call_method(
+            party.account,
+            "deposit_batch",
+            args!(Expression::entire_worktop()),
+        )
+        .build();
+    execute(test_runner, party, manifest)
+}
+
+/// Withdraws the funds owed to a party from the escrow with its obligation NFT.
+fn withdraw(
+    test_runner: &mut TestRunner<TypedInMemorySubstateStore>,
+    escrow: ComponentAddress,
+    party: &Party,
+    obligation_nft: ResourceAddress,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new(&NetworkDefinition::simulator())
+        .lock_fee(10.into(), SYS_FAUCET_COMPONENT)
+        .create_proof_from_account(obligation_nft, party.account)
+        .pop_from_auth_zone(|builder, proof_id| {
+            builder.call_method(escrow, "withdraw", args!(Proof(proof_id)))
+        })
+        .call_method(
+            party.account,
+            "deposit_batch",
+            args!(Expression::entire_worktop()),
+        )
+        .build();
+    execute(test_runner, party, manifest)
+}
+
+/// Gets the balance of the given account in the given resource.
+fn balance(
+    test_runner: &mut TestRunner<TypedInMemorySubstateStore>,
+    account: ComponentAddress,
+    resource_address: ResourceAddress,
+) -> Decimal {
+    test_runner
+        .get_component_resources(account)
+        .get(&resource_address)
+        .cloned()
+        .unwrap_or(Decimal::zero())
+}
+
+#[test]
+fn fungible_obligations_are_exchanged_once_both_parties_deposit() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let token_1 = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+    let token_2 = test_runner.create_fungible_resource(dec!("1000"), 18, party_2.account);
+
+    let (escrow, obligation_nft) = instantiate_escrow(
+        &mut test_runner,
+        package_address,
+        &party_1,
+        &party_2,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("20"),
+        },
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    );
+
+    // The first party sends more than it owes, and gets the excess back
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_1,
+        obligation_nft,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("25"),
+        },
+    )
+    .expect_commit_success();
+    assert_eq!(balance(&mut test_runner, party_1.account, token_1), dec!("980"));
+
+    // Nothing can be withdrawn until the second party deposits as well
+    withdraw(&mut test_runner, escrow, &party_1, obligation_nft).expect_commit_failure();
+
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_2,
+        obligation_nft,
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    )
+    .expect_commit_success();
+
+    // Each party now gets what the other party paid
+    withdraw(&mut test_runner, escrow, &party_1, obligation_nft).expect_commit_success();
+    withdraw(&mut test_runner, escrow, &party_2, obligation_nft).expect_commit_success();
+    assert_eq!(balance(&mut test_runner, party_1.account, token_2), dec!("300"));
+    assert_eq!(balance(&mut test_runner, party_2.account, token_1), dec!("20"));
+    assert_eq!(balance(&mut test_runner, party_2.account, token_2), dec!("700"));
+}
+
+#[test]
+fn non_fungible_obligations_are_exchanged_once_both_parties_deposit() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let nft = test_runner.create_non_fungible_resource(party_1.account);
+    let token = test_runner.create_fungible_resource(dec!("1000"), 18, party_2.account);
+    let nft_ids = BTreeSet::from([NonFungibleId::from_u32(1), NonFungibleId::from_u32(2)]);
+
+    let (escrow, obligation_nft) = instantiate_escrow(
+        &mut test_runner,
+        package_address,
+        &party_1,
+        &party_2,
+        ResourceSpecifier::NonFungible {
+            resource_address: nft,
+            non_fungible_ids: nft_ids.clone(),
+        },
+        ResourceSpecifier::Fungible {
+            resource_address: token,
+            amount: dec!("100"),
+        },
+    );
+
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_1,
+        obligation_nft,
+        ResourceSpecifier::NonFungible {
+            resource_address: nft,
+            non_fungible_ids: nft_ids,
+        },
+    )
+    .expect_commit_success();
+
+    // The escrow is not fulfilled by the non-fungible obligation alone
+    withdraw(&mut test_runner, escrow, &party_1, obligation_nft).expect_commit_failure();
+
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_2,
+        obligation_nft,
+        ResourceSpecifier::Fungible {
+            resource_address: token,
+            amount: dec!("100"),
+        },
+    )
+    .expect_commit_success();
+
+    withdraw(&mut test_runner, escrow, &party_1, obligation_nft).expect_commit_success();
+    withdraw(&mut test_runner, escrow, &party_2, obligation_nft).expect_commit_success();
+    assert_eq!(balance(&mut test_runner, party_1.account, token), dec!("100"));
+    assert_eq!(balance(&mut test_runner, party_2.account, nft), dec!("2"));
+}
+
+#[test]
+fn instantiating_with_invalid_resource_specifiers_fails() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, _party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let token = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+    let nft = test_runner.create_non_fungible_resource(party_1.account);
+
+    let valid = ResourceSpecifier::Fungible {
+        resource_address: token,
+        amount: dec!("10"),
+    };
+    let invalid_specifier_pairs = [
+        // A fungible amount which is not positive
+        (
+            ResourceSpecifier::Fungible {
+                resource_address: token,
+                amount: dec!("0"),
+            },
+            valid.clone(),
+        ),
+        // An empty set of non-fungible ids
+        (
+            valid.clone(),
+            ResourceSpecifier::NonFungible {
+                resource_address: nft,
+                non_fungible_ids: BTreeSet::new(),
+            },
+        ),
+        // The two parties paying the exact same thing
+        (valid.clone(), valid.clone()),
+    ];
+
+    for (to_be_paid_by_party_1, to_be_paid_by_party_2) in invalid_specifier_pairs {
+        let manifest = ManifestBuilder::new(&NetworkDefinition::simulator())
+            .lock_fee(10.into(), SYS_FAUCET_COMPONENT)
+            .call_function(
+                package_address,
+                "Escrow",
+                "instantiate_escrow",
+                args!(to_be_paid_by_party_1, to_be_paid_by_party_2),
+            )
+            .call_method(
+                party_1.account,
+                "deposit_batch",
+                args!(Expression::entire_worktop()),
+            )
+            .build();
+        execute(&mut test_runner, &party_1, manifest).expect_commit_failure();
+    }
+}
+
+#[test]
+fn depositing_with_an_invalid_obligation_badge_fails() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let token_1 = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+    let token_2 = test_runner.create_fungible_resource(dec!("1000"), 18, party_2.account);
+    let fake_badge = test_runner.create_non_fungible_resource(party_1.account);
+
+    let (escrow, _obligation_nft) = instantiate_escrow(
+        &mut test_runner,
+        package_address,
+        &party_1,
+        &party_2,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("20"),
+        },
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    );
+
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_1,
+        fake_badge,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("20"),
+        },
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn depositing_the_wrong_resource_fails() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let token_1 = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+    let token_2 = test_runner.create_fungible_resource(dec!("1000"), 18, party_2.account);
+    let other_token = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+
+    let (escrow, obligation_nft) = instantiate_escrow(
+        &mut test_runner,
+        package_address,
+        &party_1,
+        &party_2,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("20"),
+        },
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    );
+
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_1,
+        obligation_nft,
+        ResourceSpecifier::Fungible {
+            resource_address: other_token,
+            amount: dec!("20"),
+        },
+    )
+    .expect_commit_failure();
+}
+
+#[test]
+fn depositing_twice_fails() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let token_1 = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+    let token_2 = test_runner.create_fungible_resource(dec!("1000"), 18, party_2.account);
+
+    let (escrow, obligation_nft) = instantiate_escrow(
+        &mut test_runner,
+        package_address,
+        &party_1,
+        &party_2,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("20"),
+        },
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    );
+
+    let funds = ResourceSpecifier::Fungible {
+        resource_address: token_1,
+        amount: dec!("20"),
+    };
+    deposit(&mut test_runner, escrow, &party_1, obligation_nft, funds.clone())
+        .expect_commit_success();
+    deposit(&mut test_runner, escrow, &party_1, obligation_nft, funds).expect_commit_failure();
+}
+
+#[test]
+fn depositing_after_the_other_party_withdraws_fails() {
+    // Setup the environment
+    let mut store = TypedInMemorySubstateStore::with_bootstrap();
+    let mut test_runner = TestRunner::new(true, &mut store);
+    let (party_1, party_2) = new_parties(&mut test_runner);
+    let package_address = test_runner.compile_and_publish(this_package!());
+    let token_1 = test_runner.create_fungible_resource(dec!("1000"), 18, party_1.account);
+    let token_2 = test_runner.create_fungible_resource(dec!("1000"), 18, party_2.account);
+
+    let (escrow, obligation_nft) = instantiate_escrow(
+        &mut test_runner,
+        package_address,
+        &party_1,
+        &party_2,
+        ResourceSpecifier::Fungible {
+            resource_address: token_1,
+            amount: dec!("20"),
+        },
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    );
+
+    let funds = ResourceSpecifier::Fungible {
+        resource_address: token_1,
+        amount: dec!("20"),
+    };
+    deposit(&mut test_runner, escrow, &party_1, obligation_nft, funds.clone())
+        .expect_commit_success();
+    deposit(
+        &mut test_runner,
+        escrow,
+        &party_2,
+        obligation_nft,
+        ResourceSpecifier::Fungible {
+            resource_address: token_2,
+            amount: dec!("300"),
+        },
+    )
+    .expect_commit_success();
+
+    // The second party takes the funds of the first party, emptying the vault of its obligation,
+    // which must not let the first party pay a second time
+    withdraw(&mut test_runner, escrow, &party_2, obligation_nft).expect_commit_success();
+    deposit(&mut test_runner, escrow, &party_1, obligation_nft, funds).expect_commit_failure();
+    assert_eq!(balance(&mut test_runner, party_1.account, token_1), dec!("980"));
+}
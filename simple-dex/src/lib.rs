@@ -98,6 +98,36 @@ blueprint! {
             output_tokens_vault.take(output_amount)
         }
 
+        /// Swaps a batch of orders, each made up of input tokens and a minimum output amount.
+        ///
+        /// The orders are filled sequentially against the reserves as updated by the previous
+        /// orders. If any order yields less than its minimum output then the whole batch fails and
+        /// none of the swaps take place.
+        pub fn swap_batch(&mut self, orders: Vec<(Bucket, Decimal)>) -> Vec<Bucket> {
+            assert!(!orders.is_empty(), "You must pass in at least one order");
+
+            orders
+                .into_iter()
+                .enumerate()
+                .map(|(index, (input_tokens, minimum_output))| {
+                    assert!(
+                        minimum_output >= dec!("0"),
+                        "Invalid minimum output for order {}",
+                        index
+                    );
+
+                    // Swap against the current reserves and ensure the order's bound is respected
+                    let output_tokens: Bucket = self.swap(input_tokens);
+                    assert!(
+                        output_tokens.amount() >= minimum_output,
+                        "Order {} received less than its minimum output",
+                        index
+                    );
+                    output_tokens
+                })
+                .collect()
+        }
+
         /// Removes the amount of funds from the pool corresponding to the pool shares.
         pub fn remove_liquidity(&mut self, pool_shares: Bucket) -> (Bucket, Bucket) {
             assert!(
@@ -0,0 +1,33 @@
+use scrypto::prelude::*;
+use scrypto_unit::*;
+use transaction::builder::ManifestBuilder;
+
+#[test]
+fn instantiating_creates_the_useful_token_supply_in_the_component() {
+    // Setup the environment
+    let mut test_runner = TestRunner::new(true);
+    let (public_key, _private_key, _account_component) = test_runner.new_allocated_account();
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    // Instantiate the component
+    let manifest = ManifestBuilder::new()
+        .call_function(package_address, "TokenSale", "instantiate_token_sale", args!())
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    println!("{:?}\n", receipt);
+    receipt.expect_commit_success();
+    let component = receipt.expect_commit().entity_changes.new_component_addresses[0];
+    let useful_token = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    // The whole supply of UsefulTokens is kept by the component
+    let component_resources = test_runner.get_component_resources(component);
+    assert_eq!(component_resources.get(&useful_token), Some(&dec!("1000")));
+
+    // The UsefulToken has its metadata set
+    let metadata = test_runner.get_metadata(GlobalAddress::Resource(useful_token));
+    assert_eq!(metadata.get("name"), Some(&"UsefulToken".to_string()));
+    assert_eq!(metadata.get("symbol"), Some(&"USEFUL".to_string()));
+}
@@ -0,0 +1,106 @@
+use radix_engine::transaction::TransactionReceipt;
+use scrypto::prelude::*;
+use scrypto_unit::*;
+use transaction::builder::ManifestBuilder;
+
+// Instantiates a token sale with the given price per token, returning the test runner along with
+// the public key and account of the user and the addresses of the component and the UsefulToken.
+fn setup(
+    price_per_token: Decimal,
+) -> (TestRunner, EcdsaSecp256k1PublicKey, ComponentAddress, ComponentAddress, ResourceAddress) {
+    let mut test_runner = TestRunner::new(true);
+    let (public_key, _private_key, account_component) = test_runner.new_allocated_account();
+    let package_address = test_runner.compile_and_publish(this_package!());
+
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            package_address,
+            "TokenSale",
+            "instantiate_token_sale",
+            args!(price_per_token),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(&public_key)],
+    );
+    println!("{:?}\n", receipt);
+    receipt.expect_commit_success();
+    let component = receipt.expect_commit().entity_changes.new_component_addresses[0];
+    let useful_token = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    (test_runner, public_key, account_component, component, useful_token)
+}
+
+// Buys UsefulTokens with the given amount of XRD, depositing them into the account.
+fn buy(
+    test_runner: &mut TestRunner,
+    public_key: &EcdsaSecp256k1PublicKey,
+    account_component: ComponentAddress,
+    component: ComponentAddress,
+    amount: Decimal,
+) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account_by_amount(account_component, amount, RADIX_TOKEN)
+        .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+            builder.call_method(component, "buy", args!(bucket_id))
+        })
+        .call_method(
+            account_component,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = test_runner.execute_manifest_ignoring_fee(
+        manifest,
+        vec![NonFungibleGlobalId::from_public_key(public_key)],
+    );
+    println!("{:?}\n", receipt);
+    receipt
+}
+
+#[test]
+fn instantiating_creates_the_useful_token_supply_in_the_component() {
+    let (mut test_runner, _public_key, _account_component, component, useful_token) =
+        setup(dec!("0.5"));
+
+    let component_resources = test_runner.get_component_resources(component);
+    assert_eq!(component_resources.get(&useful_token), Some(&dec!("1000")));
+
+    let metadata = test_runner.get_metadata(GlobalAddress::Resource(useful_token));
+    assert_eq!(metadata.get("name"), Some(&"UsefulToken".to_string()));
+    assert_eq!(metadata.get("symbol"), Some(&"USEFUL".to_string()));
+}
+
+#[test]
+fn buying_gives_the_payment_divided_by_the_price_in_tokens() {
+    let (mut test_runner, public_key, account_component, component, useful_token) =
+        setup(dec!("0.5"));
+    let xrd_balance = test_runner.account_balance(account_component, RADIX_TOKEN).unwrap();
+
+    buy(&mut test_runner, &public_key, account_component, component, dec!("50"))
+        .expect_commit_success();
+
+    // 50 XRD at 0.5 XRD per token buys 100 tokens, and the payment stays in the component
+    assert_eq!(
+        test_runner.account_balance(account_component, useful_token),
+        Some(dec!("100"))
+    );
+    assert_eq!(
+        test_runner.account_balance(account_component, RADIX_TOKEN),
+        Some(xrd_balance - dec!("50"))
+    );
+    let component_resources = test_runner.get_component_resources(component);
+    assert_eq!(component_resources.get(&useful_token), Some(&dec!("900")));
+    assert_eq!(component_resources.get(&RADIX_TOKEN), Some(&dec!("50")));
+}
+
+#[test]
+fn buying_more_tokens_than_are_left_fails() {
+    let (mut test_runner, public_key, account_component, component, _useful_token) =
+        setup(dec!("0.5"));
+
+    // 600 XRD at 0.5 XRD per token would buy 1200 tokens out of the 1000 for sale
+    buy(&mut test_runner, &public_key, account_component, component, dec!("600"))
+        .expect_commit_failure();
+}
@@ -1,43 +1,505 @@
+mod mock_oracle;
+mod nft_sale;
+mod pricing;
+mod radiswap;
+
+pub use pricing::*;
 use scrypto::prelude::*;
 
+// The methods which can only be called with the seller badge. Their access rules are updated to
+// require the new seller badge whenever the seller badge is rotated or recovered.
+const SELLER_METHODS: &[&str] = &[
+    "mint_role_badge",
+    "configure_multisig_withdrawals",
+    "add_payment_resource",
+    "close_sale",
+    "withdraw_unsold_tokens",
+    "mint_allowlist_badge",
+    "configure_presale",
+    "set_purchase_limits",
+    "configure_vesting",
+    "configure_soft_cap",
+    "configure_referrals",
+    "register_referrer",
+    "configure_recovery",
+    "rotate_seller_badge",
+    "configure_beneficiaries",
+    "configure_liquidity_seeding",
+    "withdraw_liquidity_shares"
+];
+
+// The methods which can only be called with the price manager badge, and those which can only be
+// called with the pauser badge. Their access rules are updated to require new role badges whenever
+// the seller badge is recovered.
+const PRICE_MANAGER_METHODS: &[&str] = &[
+    "change_price",
+    "start_dutch_auction",
+    "start_bonding_curve",
+    "start_usd_pricing",
+    "start_tiered_pricing"
+];
+const PAUSER_METHODS: &[&str] = &["pause", "resume"];
+
+// The initial supply of pool shares of the pool seeded when the sale is finalized, all of which
+// represent the liquidity provided by the sale.
+const POOL_SHARES_INITIAL_SUPPLY: u32 = 100;
+
 #[blueprint]
 mod token_sale {
     struct TokenSale {
-        // The vault where the UsefulTokens will be stored.
-        useful_tokens_vault: Vault,
+        // The vault where the tokens being sold will be stored. This vault stays empty for
+        // mint-on-demand sales as their tokens are only minted when they are bought.
+        sale_tokens_vault: Vault,
+
+        // The vault where the badge with the authority to mint the tokens being sold is stored,
+        // only present for mint-on-demand sales.
+        minter_badge_vault: Option<Vault>,
+
+        // The maximum amount of tokens which this sale can sell.
+        supply_cap: Decimal,
+
+        // The amount of tokens sold so far.
+        tokens_sold: Decimal,
+
+        // The amount of tokens sold along the bonding curve of each payment resource in which the
+        // tokens can be sold back, net of the tokens sold back. Only these tokens can be sold back,
+        // and the reserve of each payment resource covers buying all of them back.
+        curve_tokens_sold: HashMap<ResourceAddress, Decimal>,
+
+        // The total amount paid for the tokens sold so far in each payment resource, net of the
+        // payments returned through sell-backs and refunds.
+        total_raised: HashMap<ResourceAddress, Decimal>,
+
+        // The number of buyers so far, counted as the buyer receipts with which at least one
+        // purchase was made.
+        buyer_count: u64,
+
+        // The vaults where the payments will be stored, one for each accepted payment resource.
+        payment_vaults: HashMap<ResourceAddress, Vault>,
+
+        // The pricing of the tokens in each of the accepted payment resources.
+        pricing: HashMap<ResourceAddress, Pricing>,
+
+        // The first and last epochs in which tokens can be bought from this sale.
+        start_epoch: u64,
+        end_epoch: u64,
+
+        // Whether the seller has closed this sale, after which no more tokens can be bought.
+        is_closed: bool,
+
+        // Whether the sale is paused, during which no tokens can be bought until it is resumed.
+        is_paused: bool,
+
+        // The vault where the badge with the authority to mint the allowlist badges is stored.
+        internal_admin_badge: Vault,
+
+        // The resource address of the allowlist badges which allow their holders to take part in
+        // the presale, along with the number of allowlist badges minted so far.
+        allowlist_resource: ResourceAddress,
+        allowlist_badges_minted: u64,
+
+        // The epoch at which the presale ends and the public sale starts. There is no presale when
+        // this is the start epoch of the sale.
+        public_sale_epoch: u64,
+
+        // The amount of tokens bought in the presale by each of the allowlist badges.
+        presale_purchases: HashMap<NonFungibleLocalId, Decimal>,
 
-        // The vault where the xrd payments will be stored.
-        xrd_tokens_vault: Vault,
+        // The resource address of the buyer receipts which record the purchases of each buyer,
+        // along with the number of buyer receipts minted so far.
+        buyer_receipt_resource: ResourceAddress,
+        buyer_receipts_minted: u64,
 
-        // The price of a single UsefulToken.
-        price_per_token: Decimal
+        // The minimum and maximum amount of tokens which each buyer can buy in total.
+        min_purchase: Decimal,
+        max_purchase: Decimal,
+
+        // The buyer receipts of the registered buyers, mapped to the id of the allowlist badge with
+        // which each of them registered. Only registered buyers can buy while the purchases are
+        // capped, so that each allowlist badge only ever gets one capped receipt.
+        registered_buyers: HashMap<NonFungibleLocalId, NonFungibleLocalId>,
+
+        // The number of epochs after a purchase until the cliff and until the purchased tokens are
+        // fully vested, only present when the purchased tokens vest instead of being delivered.
+        vesting_schedule: Option<(u64, u64)>,
+
+        // The vault where the purchased tokens are locked until they are claimed, along with the
+        // resource address of the vesting NFTs given to the buyers instead of the tokens and the
+        // number of vesting NFTs minted so far.
+        vesting_vault: Vault,
+        vesting_resource: ResourceAddress,
+        vesting_nfts_minted: u64,
+
+        // The minimum amount of tokens which must be sold for the sale to succeed, only present
+        // when the sale has a soft cap. The proceeds of such a sale are locked until it ends, and
+        // are refunded to the buyers if the soft cap is not reached.
+        soft_cap: Option<Decimal>,
+
+        // The resource address of the seller badge which makes its holder the owner of the sale.
+        // This changes whenever the seller badge is rotated or recovered.
+        seller_badge: ResourceAddress,
+
+        // The resource address of the recovery guardian badges, only present once the seller badge
+        // can be recovered by the guardians.
+        recovery_guardian_badge: Option<ResourceAddress>,
+
+        // The resource addresses of the badges of the roles which the seller, as the owner of the
+        // sale, can hand out to separate the administration duties.
+        price_manager_badge: ResourceAddress,
+        treasurer_badge: ResourceAddress,
+        pauser_badge: ResourceAddress,
+
+        // The resource address of the treasury signer badges, only present once withdrawals
+        // require the proofs of several treasury signers instead of a treasurer badge.
+        treasury_signer_badge: Option<ResourceAddress>,
+
+        // The resource address of the referrer badges identifying the referrers, along with the
+        // number of referrer badges minted so far.
+        referrer_badge: ResourceAddress,
+        referrers_registered: u64,
+
+        // The share of each referred purchase which is paid to the referrer and how it is paid,
+        // only present when the sale has a referral program.
+        referral_program: Option<(Decimal, ReferralReward)>,
+
+        // The vaults where the rewards of the referrers are kept until they claim them, along with
+        // the rewards owed to each of the referrers in each resource.
+        referral_rewards_vaults: HashMap<ResourceAddress, Vault>,
+        referral_rewards: HashMap<NonFungibleLocalId, HashMap<ResourceAddress, Decimal>>,
+
+        // The resource address of the beneficiary badges, only present once the proceeds are split
+        // between beneficiaries, along with the share of each payment which goes to each of the
+        // beneficiaries and the vaults where their shares are kept until they withdraw them.
+        beneficiary_badge: Option<ResourceAddress>,
+        beneficiary_shares: HashMap<NonFungibleLocalId, Decimal>,
+        beneficiary_vaults: HashMap<NonFungibleLocalId, HashMap<ResourceAddress, Vault>>,
+
+        // How the liquidity of a pool is seeded when the sale is finalized, only present when the
+        // sale seeds liquidity, along with the vaults where the tokens and the share of the
+        // proceeds reserved for the pool are kept until then.
+        liquidity_seeding: Option<LiquiditySeeding>,
+        liquidity_tokens_vault: Vault,
+        liquidity_proceeds_vault: Option<Vault>,
+
+        // The pool created when the sale is finalized along with the epoch at which its pool
+        // shares unlock, and the vault where the pool shares are locked until then.
+        liquidity_pool: Option<(ComponentAddress, u64)>,
+        liquidity_shares_vault: Option<Vault>
     }
 
     impl TokenSale {
-        pub fn instantiate_token_sale(price_per_token: Decimal) -> (ComponentAddress, Bucket) {
+        pub fn instantiate_token_sale(
+            name: String,
+            symbol: String,
+            description: String,
+            icon_url: String,
+            divisibility: u8,
+            initial_supply: Decimal,
+            payment_resource: ResourceAddress,
+            price_per_token: Decimal,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            assert!(initial_supply > Decimal::zero(), "The initial supply must be positive");
 
-            // Creating a new token called "UsefulToken"
+            // Creating a new token with the given parameters
             let my_bucket: Bucket = ResourceBuilder::new_fungible()
-                .metadata("name", "UsefulToken")
-                .metadata("symbol", "USEFUL")
-                .mint_initial_supply(1000);
+                .divisibility(divisibility)
+                .metadata("name", name)
+                .metadata("symbol", symbol)
+                .metadata("description", description)
+                .metadata("icon_url", icon_url)
+                .mint_initial_supply(initial_supply);
 
-            // Creating a new seller badge which we will give the withdraw authority to
+            Self::instantiate_token_sale_with_tokens(
+                my_bucket,
+                payment_resource,
+                price_per_token,
+                start_epoch,
+                end_epoch
+            )
+        }
+
+        pub fn instantiate_mint_on_demand_token_sale(
+            name: String,
+            symbol: String,
+            description: String,
+            icon_url: String,
+            divisibility: u8,
+            supply_cap: Decimal,
+            payment_resource: ResourceAddress,
+            price_per_token: Decimal,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            assert!(supply_cap > Decimal::zero(), "The supply cap must be positive");
+
+            // Creating a new badge which the component will use to mint the tokens as they are bought
+            let minter_badge: Bucket = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "Minter Badge")
+                .mint_initial_supply(1);
+
+            // Creating a new token with the given parameters and without any initial supply
+            let token_resource: ResourceAddress = ResourceBuilder::new_fungible()
+                .divisibility(divisibility)
+                .metadata("name", name)
+                .metadata("symbol", symbol)
+                .metadata("description", description)
+                .metadata("icon_url", icon_url)
+                .mintable(rule!(require(minter_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+
+            let mut pricing: HashMap<ResourceAddress, Pricing> = HashMap::new();
+            pricing.insert(payment_resource, Pricing::Fixed(price_per_token));
+
+            Self::instantiate(
+                Vault::new(token_resource),
+                Some(Vault::with_bucket(minter_badge)),
+                supply_cap,
+                pricing,
+                start_epoch,
+                end_epoch
+            )
+        }
+
+        pub fn instantiate_token_sale_with_tokens(
+            tokens_for_sale: Bucket,
+            payment_resource: ResourceAddress,
+            price_per_token: Decimal,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            let mut prices_per_token: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            prices_per_token.insert(payment_resource, price_per_token);
+
+            Self::instantiate_multi_payment_token_sale(tokens_for_sale, prices_per_token, start_epoch, end_epoch)
+        }
+
+        pub fn instantiate_multi_payment_token_sale(
+            tokens_for_sale: Bucket,
+            prices_per_token: HashMap<ResourceAddress, Decimal>,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            let pricing: HashMap<ResourceAddress, Pricing> = prices_per_token
+                .into_iter()
+                .map(|(resource_address, price)| (resource_address, Pricing::Fixed(price)))
+                .collect();
+
+            Self::instantiate_with_pricing(tokens_for_sale, pricing, start_epoch, end_epoch)
+        }
+
+        // Creates a sale whose tokens are priced in USD, where the amount of the payment resource
+        // which buys the tokens is converted from their USD price through a price oracle.
+        pub fn instantiate_usd_token_sale(
+            tokens_for_sale: Bucket,
+            payment_resource: ResourceAddress,
+            usd_price_per_token: Decimal,
+            oracle: ComponentAddress,
+            max_staleness_epochs: u64,
+            fallback: OracleFallback,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            let mut pricing: HashMap<ResourceAddress, Pricing> = HashMap::new();
+            pricing.insert(
+                payment_resource,
+                Pricing::UsdPrice {
+                    usd_price: usd_price_per_token,
+                    oracle: oracle,
+                    max_staleness_epochs: max_staleness_epochs,
+                    fallback: fallback
+                }
+            );
+
+            Self::instantiate_with_pricing(tokens_for_sale, pricing, start_epoch, end_epoch)
+        }
+
+        fn instantiate_with_pricing(
+            tokens_for_sale: Bucket,
+            pricing: HashMap<ResourceAddress, Pricing>,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            assert!(
+                matches!(
+                    borrow_resource_manager!(tokens_for_sale.resource_address()).resource_type(),
+                    ResourceType::Fungible { .. }
+                ),
+                "Only fungible tokens can be sold"
+            );
+            assert!(!tokens_for_sale.is_empty(), "You must pass in the tokens to sell");
+            let supply_cap: Decimal = tokens_for_sale.amount();
+            Self::instantiate(
+                Vault::with_bucket(tokens_for_sale),
+                None,
+                supply_cap,
+                pricing,
+                start_epoch,
+                end_epoch
+            )
+        }
+
+        fn instantiate(
+            sale_tokens_vault: Vault,
+            minter_badge_vault: Option<Vault>,
+            supply_cap: Decimal,
+            pricing: HashMap<ResourceAddress, Pricing>,
+            start_epoch: u64,
+            end_epoch: u64
+        ) -> (ComponentAddress, Bucket) {
+            assert!(!pricing.is_empty(), "At least one payment resource must be accepted");
+            assert!(start_epoch <= end_epoch, "The sale must not end before it starts");
+            for resource_pricing in pricing.values() {
+                resource_pricing.validate();
+            }
+
+            // Creating a new seller badge which makes its holder the owner of the sale
             let seller_badge: Bucket = ResourceBuilder::new_fungible()
                 .metadata("name", "Seller Badge")
                 .metadata("symbol", "SELLER")
                 .mint_initial_supply(1);
 
-            // Setting the access rules to only allow the seller badge to withdraw the funds or change the price
-            let access_rules: AccessRules = AccessRules::new()
-                .method("withdraw_funds", rule!(require(seller_badge.resource_address())), LOCKED)
-                .method("change_price", rule!(require(seller_badge.resource_address())), LOCKED)
+            // Creating a new internal admin badge and the allowlist badge resource which it can mint
+            let internal_admin_badge: Bucket = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "Internal Admin Badge")
+                .mint_initial_supply(1);
+            let allowlist_resource: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Allowlist Badge")
+                .metadata("description", "A badge which allows its holder to take part in the presale")
+                .mintable(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+            let buyer_receipt_resource: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Buyer Receipt")
+                .metadata("description", "A receipt recording the tokens bought by its holder")
+                .mintable(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .updateable_non_fungible_data(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .burnable(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+            let referrer_badge: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Referrer Badge")
+                .metadata("description", "A badge identifying a referrer of the sale")
+                .mintable(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+            let vesting_resource: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Vesting NFT")
+                .metadata("description", "An NFT entitling its holder to claim tokens as they vest")
+                .mintable(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .updateable_non_fungible_data(rule!(require(internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+
+            // Creating the badges of the roles which the owner can mint and recall
+            let price_manager_badge: ResourceAddress = Self::create_role_badge(
+                "Price Manager Badge",
+                seller_badge.resource_address(),
+                internal_admin_badge.resource_address()
+            );
+            let treasurer_badge: ResourceAddress = Self::create_role_badge(
+                "Treasurer Badge",
+                seller_badge.resource_address(),
+                internal_admin_badge.resource_address()
+            );
+            let pauser_badge: ResourceAddress = Self::create_role_badge(
+                "Pauser Badge",
+                seller_badge.resource_address(),
+                internal_admin_badge.resource_address()
+            );
+
+            // Setting the access rules to only allow the treasurer to withdraw the funds, the price
+            // manager to change the price, the pauser to pause the sale, and the seller badge to
+            // perform the other admin-only actions. The recovery of the seller badge is denied until
+            // the seller configures the recovery guardians.
+            let mut access_rules: AccessRules = AccessRules::new()
+                .method(
+                    "withdraw_funds",
+                    rule!(require(treasurer_badge)),
+                    MUTABLE(rule!(require(internal_admin_badge.resource_address())))
+                )
+                .method(
+                    "recover_seller_badge",
+                    rule!(deny_all),
+                    MUTABLE(rule!(require(internal_admin_badge.resource_address())))
+                )
                 .default(rule!(allow_all), LOCKED);
+            for method in SELLER_METHODS {
+                access_rules = access_rules.method(
+                    method,
+                    rule!(require(seller_badge.resource_address())),
+                    MUTABLE(rule!(require(internal_admin_badge.resource_address())))
+                );
+            }
+            for method in PRICE_MANAGER_METHODS {
+                access_rules = access_rules.method(
+                    method,
+                    rule!(require(price_manager_badge)),
+                    MUTABLE(rule!(require(internal_admin_badge.resource_address())))
+                );
+            }
+            for method in PAUSER_METHODS {
+                access_rules = access_rules.method(
+                    method,
+                    rule!(require(pauser_badge)),
+                    MUTABLE(rule!(require(internal_admin_badge.resource_address())))
+                );
+            }
+
+            // Creating an empty vault for each of the accepted payment resources
+            let payment_vaults: HashMap<ResourceAddress, Vault> = pricing
+                .keys()
+                .map(|resource_address| (*resource_address, Vault::new(*resource_address)))
+                .collect();
 
+            let sale_token_resource: ResourceAddress = sale_tokens_vault.resource_address();
             let mut token_sale: TokenSaleComponent = Self {
-                useful_tokens_vault: Vault::with_bucket(my_bucket),
-                xrd_tokens_vault: Vault::new(RADIX_TOKEN),
-                price_per_token: price_per_token
+                sale_tokens_vault: sale_tokens_vault,
+                minter_badge_vault: minter_badge_vault,
+                supply_cap: supply_cap,
+                tokens_sold: Decimal::zero(),
+                curve_tokens_sold: HashMap::new(),
+                total_raised: HashMap::new(),
+                buyer_count: 0,
+                payment_vaults: payment_vaults,
+                pricing: pricing,
+                start_epoch: start_epoch,
+                end_epoch: end_epoch,
+                is_closed: false,
+                is_paused: false,
+                internal_admin_badge: Vault::with_bucket(internal_admin_badge),
+                allowlist_resource: allowlist_resource,
+                allowlist_badges_minted: 0,
+                public_sale_epoch: start_epoch,
+                presale_purchases: HashMap::new(),
+                buyer_receipt_resource: buyer_receipt_resource,
+                buyer_receipts_minted: 0,
+                min_purchase: Decimal::zero(),
+                max_purchase: Decimal::MAX,
+                registered_buyers: HashMap::new(),
+                vesting_schedule: None,
+                vesting_vault: Vault::new(sale_token_resource),
+                vesting_resource: vesting_resource,
+                vesting_nfts_minted: 0,
+                soft_cap: None,
+                seller_badge: seller_badge.resource_address(),
+                recovery_guardian_badge: None,
+                price_manager_badge: price_manager_badge,
+                treasurer_badge: treasurer_badge,
+                pauser_badge: pauser_badge,
+                treasury_signer_badge: None,
+                referrer_badge: referrer_badge,
+                referrers_registered: 0,
+                referral_program: None,
+                referral_rewards_vaults: HashMap::new(),
+                referral_rewards: HashMap::new(),
+                beneficiary_badge: None,
+                beneficiary_shares: HashMap::new(),
+                beneficiary_vaults: HashMap::new(),
+                liquidity_seeding: None,
+                liquidity_tokens_vault: Vault::new(sale_token_resource),
+                liquidity_proceeds_vault: None,
+                liquidity_pool: None,
+                liquidity_shares_vault: None
             }
             .instantiate();
             token_sale.add_access_check(access_rules);
@@ -46,18 +508,1498 @@ mod token_sale {
             return (token_sale_component_address, seller_badge)
         }
 
-        pub fn buy(&mut self, funds: Bucket) -> Bucket {
-            let purchase_amount: Decimal = funds.amount() / self.price_per_token;
-            self.xrd_tokens_vault.put(funds);
-            self.useful_tokens_vault.take(purchase_amount)
+        // Buys as many tokens as the funds allow, returning them along with a new buyer receipt
+        // recording the purchase.
+        pub fn buy(&mut self, funds: Bucket) -> (Bucket, Bucket) {
+            self.assert_sale_open();
+            self.assert_public_sale();
+            self.assert_purchases_uncapped();
+            let payment_resource: ResourceAddress = funds.resource_address();
+            let amount_paid: Decimal = funds.amount();
+            let tokens: Bucket = self.purchase(funds);
+            let receipt: Bucket = self.issue_receipt(tokens.amount(), payment_resource, amount_paid);
+            (self.deliver(tokens), receipt)
+        }
+
+        // Buys as many tokens as the funds allow like `buy`, rewarding the referrer with the given
+        // referrer badge id for the purchase.
+        pub fn buy_with_referral(&mut self, funds: Bucket, referrer_id: u64) -> (Bucket, Bucket) {
+            let payment_resource: ResourceAddress = funds.resource_address();
+            let amount_paid: Decimal = funds.amount();
+            let (tokens, receipt): (Bucket, Bucket) = self.buy(funds);
+            self.reward_referrer(referrer_id, receipt.non_fungible::<BuyerReceipt>().data().tokens_bought, payment_resource, amount_paid);
+            (tokens, receipt)
         }
 
-        pub fn withdraw_funds(&mut self, amount: Decimal) -> Bucket {
-            self.xrd_tokens_vault.take(amount)
+        // Buys as many tokens as the funds allow, recording the purchase on an existing buyer
+        // receipt so that the purchases made with it stay within the purchase limits. While the
+        // purchases are capped, only the receipts of registered buyers are accepted.
+        pub fn buy_with_receipt(&mut self, receipt_proof: Proof, funds: Bucket) -> Bucket {
+            self.assert_sale_open();
+            self.assert_public_sale();
+            let payment_resource: ResourceAddress = funds.resource_address();
+            let amount_paid: Decimal = funds.amount();
+            let tokens: Bucket = self.purchase(funds);
+            self.update_receipt(receipt_proof, tokens.amount(), payment_resource, amount_paid);
+            self.deliver(tokens)
         }
 
-        pub fn change_price(&mut self, price: Decimal) {
-            self.price_per_token = price;
+        // Buys as many tokens as the funds allow, keeping all of the funds.
+        fn purchase(&mut self, funds: Bucket) -> Bucket {
+            let purchase_amount: Decimal = self
+                .pricing(funds.resource_address())
+                .tokens_for_payment(
+                    Runtime::current_epoch(),
+                    self.pricing_position(funds.resource_address()),
+                    funds.amount()
+                )
+                .round(
+                Self::divisibility(self.sale_tokens_vault.resource_address()),
+                RoundingMode::TowardsZero
+            );
+            assert!(
+                purchase_amount <= self.remaining_supply(),
+                "Insufficient supply: only {} tokens remain for sale",
+                self.remaining_supply()
+            );
+            self.record_curve_sale(funds.resource_address(), purchase_amount);
+            self.receive_payment(funds);
+            self.take_sale_tokens(purchase_amount)
+        }
+
+        // Buys exactly `desired_tokens` tokens, taking only the required payment and returning the
+        // purchased tokens along with the change and a new buyer receipt. When fewer tokens remain
+        // than desired, the purchase either fails or, if `allow_partial_fill` is set, buys all of
+        // the remaining ones.
+        pub fn buy_amount(
+            &mut self,
+            desired_tokens: Decimal,
+            mut payment: Bucket,
+            allow_partial_fill: bool
+        ) -> (Bucket, Bucket, Bucket) {
+            self.assert_sale_open();
+            self.assert_public_sale();
+            self.assert_purchases_uncapped();
+            assert!(desired_tokens > Decimal::zero(), "The desired amount of tokens must be positive");
+            let divisibility: u32 = Self::divisibility(self.sale_tokens_vault.resource_address());
+            assert!(
+                desired_tokens == desired_tokens.round(divisibility, RoundingMode::TowardsZero),
+                "The desired amount of tokens does not respect the divisibility of the token"
+            );
+
+            let available_tokens: Decimal = self.remaining_supply();
+            let purchase_amount: Decimal = if desired_tokens <= available_tokens {
+                desired_tokens
+            } else {
+                assert!(
+                    allow_partial_fill,
+                    "Insufficient supply: only {} tokens remain for sale",
+                    available_tokens
+                );
+                available_tokens
+            };
+            assert!(purchase_amount > Decimal::zero(), "The sale has sold out");
+
+            // The required payment is rounded up so that it can be taken from the payment bucket
+            let required_payment: Decimal = self
+                .pricing(payment.resource_address())
+                .cost_of_tokens(
+                    Runtime::current_epoch(),
+                    self.pricing_position(payment.resource_address()),
+                    purchase_amount
+                )
+                .round(
+                Self::divisibility(payment.resource_address()),
+                RoundingMode::TowardsPositiveInfinity
+            );
+            assert!(
+                payment.amount() >= required_payment,
+                "Insufficient payment: {} is required but only {} was provided",
+                required_payment,
+                payment.amount()
+            );
+
+            self.record_curve_sale(payment.resource_address(), purchase_amount);
+            self.receive_payment(payment.take(required_payment));
+            let receipt: Bucket = self.issue_receipt(purchase_amount, payment.resource_address(), required_payment);
+            let tokens: Bucket = self.take_sale_tokens(purchase_amount);
+            (self.deliver(tokens), payment, receipt)
+        }
+
+        // Buys tokens during the presale, which is only open to the holders of allowlist badges.
+        // The tokens bought with an allowlist badge can not exceed the badge's allocation.
+        pub fn presale_buy(&mut self, allowlist_proof: Proof, funds: Bucket) -> (Bucket, Bucket) {
+            self.assert_sale_open();
+            assert!(
+                Runtime::current_epoch() < self.public_sale_epoch,
+                "The presale ended at epoch {}",
+                self.public_sale_epoch
+            );
+
+            let allowlist_badge: NonFungible<AllowlistEntry> = allowlist_proof
+                .validate_proof(ProofValidationMode::ValidateResourceAddress(self.allowlist_resource))
+                .expect("Invalid allowlist badge provided")
+                .non_fungible::<AllowlistEntry>();
+
+            // Buying the tokens and then checking that they fit in the allocation, the purchase failing
+            // as a whole otherwise.
+            let payment_resource: ResourceAddress = funds.resource_address();
+            let amount_paid: Decimal = funds.amount();
+            let tokens: Bucket = self.purchase(funds);
+            let purchased: &mut Decimal = self.presale_purchases
+                .entry(allowlist_badge.local_id().clone())
+                .or_insert(Decimal::zero());
+            *purchased += tokens.amount();
+            assert!(
+                *purchased <= allowlist_badge.data().allocation,
+                "The purchase exceeds the allocation of {} tokens of this allowlist badge",
+                allowlist_badge.data().allocation
+            );
+
+            let receipt: Bucket = self.issue_receipt(tokens.amount(), payment_resource, amount_paid);
+            (self.deliver(tokens), receipt)
+        }
+
+        pub fn tokens_sold(&self) -> Decimal {
+            self.tokens_sold
+        }
+
+        // Gets the total amount paid for the tokens sold so far in each payment resource, net of
+        // the payments returned through sell-backs and refunds.
+        pub fn total_raised(&self) -> HashMap<ResourceAddress, Decimal> {
+            self.total_raised.clone()
+        }
+
+        // Gets the number of buyers so far. Buyers are identified by their buyer receipts, so a
+        // buyer who buys again through `buy_with_receipt` counts once, while a buyer who buys again
+        // through `buy` gets a new receipt and counts again.
+        pub fn buyer_count(&self) -> u64 {
+            self.buyer_count
+        }
+
+        pub fn sale_phase(&self) -> SalePhase {
+            let current_epoch: u64 = Runtime::current_epoch();
+            if self.is_closed {
+                SalePhase::Closed
+            } else if current_epoch > self.end_epoch {
+                SalePhase::Ended
+            } else if self.is_paused {
+                SalePhase::Paused
+            } else if current_epoch < self.start_epoch {
+                SalePhase::NotStarted
+            } else if current_epoch < self.public_sale_epoch {
+                SalePhase::Presale
+            } else {
+                SalePhase::PublicSale
+            }
+        }
+
+        pub fn remaining_supply(&self) -> Decimal {
+            self.supply_cap - self.tokens_sold
+        }
+
+        pub fn withdraw_funds(&mut self, payment_resource: ResourceAddress, amount: Decimal) -> Bucket {
+            if let Some(soft_cap) = self.soft_cap {
+                assert!(self.has_ended(), "The proceeds are locked until the sale ends");
+                assert!(
+                    self.tokens_sold >= soft_cap,
+                    "The soft cap of {} tokens was not reached, the proceeds are refunded to the buyers",
+                    soft_cap
+                );
+            }
+
+            // When tokens can be sold back along a bonding curve, the funds needed to buy back all
+            // of the tokens sold along the curve are kept as a reserve.
+            let reserve: Decimal = match self.pricing(payment_resource) {
+                Pricing::BondingCurve {
+                    curve,
+                    sell_back_enabled: true
+                } => curve.integral(self.curve_tokens_sold(payment_resource)),
+                _ => Decimal::zero()
+            };
+
+            let payment_vault: &mut Vault = self.payment_vaults.get_mut(&payment_resource).unwrap();
+            assert!(
+                payment_vault.amount() - amount >= reserve,
+                "Only {} can be withdrawn without touching the reserve",
+                payment_vault.amount() - reserve
+            );
+            payment_vault.take(amount)
+        }
+
+        pub fn change_price(&mut self, payment_resource: ResourceAddress, price: Decimal) {
+            self.set_pricing(payment_resource, Pricing::Fixed(price));
+        }
+
+        // Prices the tokens through a Dutch auction in the given payment resource, where the price
+        // decays from the start price down to the floor price between the given epochs.
+        pub fn start_dutch_auction(
+            &mut self,
+            payment_resource: ResourceAddress,
+            start_price: Decimal,
+            floor_price: Decimal,
+            start_epoch: u64,
+            end_epoch: u64,
+            decay: PriceDecay
+        ) {
+            self.set_pricing(
+                payment_resource,
+                Pricing::DutchAuction {
+                    start_price: start_price,
+                    floor_price: floor_price,
+                    start_epoch: start_epoch,
+                    end_epoch: end_epoch,
+                    decay: decay
+                }
+            );
+        }
+
+        // Prices the tokens through a bonding curve in the given payment resource, where the price
+        // follows the curve based on the amount of tokens sold. When `sell_back_enabled` is set,
+        // holders can sell their tokens back along the curve through the `sell` method, and the
+        // curve is then priced along the tokens bought through it alone.
+        pub fn start_bonding_curve(
+            &mut self,
+            payment_resource: ResourceAddress,
+            curve: BondingCurve,
+            sell_back_enabled: bool
+        ) {
+            assert!(
+                !(sell_back_enabled && self.beneficiary_badge.is_some()),
+                "Selling back can not be combined with beneficiaries"
+            );
+            assert!(
+                !(sell_back_enabled && self.liquidity_seeding.is_some()),
+                "Selling back can not be combined with liquidity seeding"
+            );
+            assert!(
+                !(sell_back_enabled && self.soft_cap.is_some()),
+                "Selling back can not be combined with a soft cap"
+            );
+            assert!(
+                !(sell_back_enabled && matches!(self.referral_program, Some((_, ReferralReward::Commission)))),
+                "Selling back can not be combined with commissions"
+            );
+            self.set_pricing(
+                payment_resource,
+                Pricing::BondingCurve {
+                    curve: curve,
+                    sell_back_enabled: sell_back_enabled
+                }
+            );
+        }
+
+        // Prices the tokens in USD in the given payment resource, converting the USD price into the
+        // payment resource through the given price oracle. When the oracle has not been updated
+        // for more than `max_staleness_epochs`, the `fallback` decides how the tokens are priced.
+        pub fn start_usd_pricing(
+            &mut self,
+            payment_resource: ResourceAddress,
+            usd_price: Decimal,
+            oracle: ComponentAddress,
+            max_staleness_epochs: u64,
+            fallback: OracleFallback
+        ) {
+            self.set_pricing(
+                payment_resource,
+                Pricing::UsdPrice {
+                    usd_price: usd_price,
+                    oracle: oracle,
+                    max_staleness_epochs: max_staleness_epochs,
+                    fallback: fallback
+                }
+            );
+        }
+
+        // Prices the tokens in tiers in the given payment resource, where each tier is made up of
+        // the threshold from which it starts and the price of its tokens. With a `PurchaseVolume`
+        // basis the thresholds give volume discounts to larger purchases, while with a
+        // `CumulativeSales` basis the price advances as the sales cross each threshold.
+        pub fn start_tiered_pricing(
+            &mut self,
+            payment_resource: ResourceAddress,
+            tiers: Vec<(Decimal, Decimal)>,
+            basis: TierBasis
+        ) {
+            self.set_pricing(
+                payment_resource,
+                Pricing::Tiered {
+                    tiers: tiers,
+                    basis: basis
+                }
+            );
+        }
+
+        // Sells tokens back along the bonding curve of the given payment resource, paying the
+        // seller out of the reserve of the payments received in that resource. As the reserve stays
+        // in the sale, selling back remains possible once the sale has ended or was closed, and is
+        // only halted while the sale is paused.
+        pub fn sell(&mut self, tokens: Bucket, payment_resource: ResourceAddress) -> Bucket {
+            assert!(!self.is_paused, "The sale is paused");
+            assert!(
+                tokens.resource_address() == self.sale_tokens_vault.resource_address(),
+                "Only the tokens being sold can be sold back"
+            );
+
+            let curve: BondingCurve = match self.pricing(payment_resource) {
+                Pricing::BondingCurve {
+                    curve,
+                    sell_back_enabled: true
+                } => curve.clone(),
+                _ => panic!("Selling back is not enabled for the given payment resource")
+            };
+            let curve_tokens_sold: Decimal = self.curve_tokens_sold(payment_resource);
+            assert!(
+                tokens.amount() <= curve_tokens_sold,
+                "More tokens are sold back than were sold along the curve"
+            );
+
+            // The payout is rounded down so that it can always be taken from the reserve
+            let payout: Decimal = curve
+                .cost_of_tokens(curve_tokens_sold - tokens.amount(), tokens.amount())
+                .round(Self::divisibility(payment_resource), RoundingMode::TowardsZero);
+            let reserve: &mut Vault = self.payment_vaults.get_mut(&payment_resource).unwrap();
+            assert!(
+                reserve.amount() >= payout,
+                "Insufficient reserve: only {} remains to pay for the tokens",
+                reserve.amount()
+            );
+            let payout: Bucket = reserve.take(payout);
+            *self.total_raised.get_mut(&payment_resource).unwrap() -= payout.amount();
+
+            self.tokens_sold -= tokens.amount();
+            *self.curve_tokens_sold.get_mut(&payment_resource).unwrap() -= tokens.amount();
+            self.sale_tokens_vault.put(tokens);
+            payout
+        }
+
+        pub fn current_price(&self, payment_resource: ResourceAddress) -> Decimal {
+            self.pricing(payment_resource)
+                .price_per_token(Runtime::current_epoch(), self.pricing_position(payment_resource))
+        }
+
+        pub fn add_payment_resource(&mut self, payment_resource: ResourceAddress, price: Decimal) {
+            let pricing: Pricing = Pricing::Fixed(price);
+            pricing.validate();
+            assert!(
+                !self.pricing.contains_key(&payment_resource),
+                "The given resource is already an accepted payment resource"
+            );
+            self.payment_vaults.insert(payment_resource, Vault::new(payment_resource));
+            self.pricing.insert(payment_resource, pricing);
+        }
+
+        // Closes the sale permanently, returning the tokens which were not sold. The sale can be
+        // closed at any time, which also serves as an emergency stop. No more tokens can be minted
+        // by a mint-on-demand sale once it is closed. The proceeds stay in the sale, to be
+        // withdrawn through `withdraw_funds` with the usual authorization and reserve checks, or
+        // to refund the buyers of a sale which did not reach its soft cap.
+        pub fn close_sale(&mut self) -> Bucket {
+            assert!(!self.is_closed, "The sale has already been closed");
+
+            self.is_closed = true;
+            self.supply_cap = self.tokens_sold;
+
+            // The tokens reserved for the liquidity are returned with the unsold tokens when no
+            // proceeds were raised to seed the liquidity with, as the sale can not be finalized
+            let mut unsold_tokens: Bucket = self.sale_tokens_vault.take_all();
+            if self.liquidity_proceeds_vault.as_ref().map(|vault| vault.is_empty()) == Some(true) {
+                unsold_tokens.put(self.liquidity_tokens_vault.take_all());
+            }
+            unsold_tokens
+        }
+
+        // Withdraws the tokens which were sold back after the sale was closed, which are no longer
+        // for sale.
+        pub fn withdraw_unsold_tokens(&mut self) -> Bucket {
+            assert!(self.is_closed, "The tokens are for sale until the sale is closed");
+            self.sale_tokens_vault.take_all()
+        }
+
+        // Mints a new allowlist badge allowing its holder to buy up to `allocation` tokens in the
+        // presale.
+        pub fn mint_allowlist_badge(&mut self, allocation: Decimal) -> Bucket {
+            assert!(allocation > Decimal::zero(), "The allocation must be positive");
+
+            self.allowlist_badges_minted += 1;
+            let allowlist_badge_id: NonFungibleLocalId = NonFungibleLocalId::Integer(self.allowlist_badges_minted);
+            self.internal_admin_badge.authorize(|| {
+                borrow_resource_manager!(self.allowlist_resource)
+                    .mint_non_fungible(&allowlist_badge_id, AllowlistEntry { allocation: allocation })
+            })
+        }
+
+        // Sets the epoch at which the presale ends and the public sale starts.
+        pub fn configure_presale(&mut self, public_sale_epoch: u64) {
+            assert!(
+                Runtime::current_epoch() < self.start_epoch,
+                "The presale can only be configured before the sale starts"
+            );
+            assert!(
+                public_sale_epoch >= self.start_epoch && public_sale_epoch <= self.end_epoch,
+                "The public sale must start during the sale"
+            );
+            self.public_sale_epoch = public_sale_epoch;
+        }
+
+        // Sets the minimum and maximum amount of tokens which each buyer can buy in total. The
+        // limits are checked against the total recorded on a buyer receipt. As anyone can get a
+        // new receipt through `buy`, capping the purchases with a maximum other than `Decimal::MAX`
+        // also requires buyers to register with an allowlist badge through `register_buyer` and
+        // to buy through `buy_with_receipt`, which caps the purchases per allowlist badge.
+        pub fn set_purchase_limits(&mut self, min_purchase: Decimal, max_purchase: Decimal) {
+            assert!(min_purchase >= Decimal::zero(), "The minimum purchase must not be negative");
+            assert!(min_purchase <= max_purchase, "The minimum purchase must not exceed the maximum");
+            self.min_purchase = min_purchase;
+            self.max_purchase = max_purchase;
+        }
+
+        // Registers the holder of an allowlist badge as a buyer, returning their buyer receipt.
+        // Each allowlist badge can only register once, and the tokens bought with it in the
+        // presale count towards the purchase limits of its receipt.
+        pub fn register_buyer(&mut self, allowlist_proof: Proof) -> Bucket {
+            let allowlist_id: NonFungibleLocalId = allowlist_proof
+                .validate_proof(ProofValidationMode::ValidateResourceAddress(self.allowlist_resource))
+                .expect("Invalid allowlist badge provided")
+                .non_fungible::<AllowlistEntry>()
+                .local_id()
+                .clone();
+            assert!(
+                !self.registered_buyers.values().any(|registered_id| *registered_id == allowlist_id),
+                "A buyer has already registered with this allowlist badge"
+            );
+
+            let (receipt_id, receipt): (NonFungibleLocalId, Bucket) = self.mint_receipt(BuyerReceipt {
+                tokens_bought: Decimal::zero(),
+                amounts_paid: HashMap::new()
+            });
+            self.registered_buyers.insert(receipt_id, allowlist_id);
+            receipt
+        }
+
+        // Pauses the sale, halting all purchases and sell-backs until it is resumed. Unlike
+        // `close_sale`, pausing is temporary, which makes it the way to halt the sale while a
+        // pricing mistake is being fixed.
+        pub fn pause(&mut self) {
+            assert!(!self.is_closed, "The sale has been closed");
+            assert!(!self.is_paused, "The sale is already paused");
+            self.is_paused = true;
+            info!("The sale was paused at epoch {}", Runtime::current_epoch());
+        }
+
+        // Resumes a paused sale, allowing purchases again.
+        pub fn resume(&mut self) {
+            assert!(!self.is_closed, "The sale has been closed");
+            assert!(self.is_paused, "The sale is not paused");
+            self.is_paused = false;
+            info!("The sale was resumed at epoch {}", Runtime::current_epoch());
+        }
+
+        pub fn is_paused(&self) -> bool {
+            self.is_paused
+        }
+
+        // Makes withdrawals require the proofs of `threshold` out of `signers` treasury signers in
+        // the same transaction, instead of the proof of a treasurer badge. Returns the badges of
+        // the treasury signers, which the owner hands out to the members of the team.
+        pub fn configure_multisig_withdrawals(&mut self, signers: u8, threshold: u8) -> Bucket {
+            assert!(
+                self.treasury_signer_badge.is_none(),
+                "Multi-signature withdrawals are already configured"
+            );
+            assert!(
+                threshold > 0 && threshold <= signers,
+                "The threshold must be between one and the number of signers"
+            );
+
+            let treasury_signer_badge: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Treasury Signer Badge")
+                .mintable(rule!(require(self.internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+            self.treasury_signer_badge = Some(treasury_signer_badge);
+
+            let mut signer_badges: Bucket = Bucket::new(treasury_signer_badge);
+            let mut signer_ids: Vec<NonFungibleGlobalId> = Vec::new();
+            for signer in 1..=signers {
+                let signer_id: NonFungibleLocalId = NonFungibleLocalId::Integer(signer as u64);
+                signer_badges.put(self.internal_admin_badge.authorize(|| {
+                    borrow_resource_manager!(treasury_signer_badge).mint_non_fungible(&signer_id, TreasurySigner {})
+                }));
+                signer_ids.push(NonFungibleGlobalId::new(treasury_signer_badge, signer_id));
+            }
+
+            // Replacing the treasurer with the treasury signers and locking the rule in place
+            let mut access_rules: ComponentAccessRules =
+                borrow_component!(Runtime::actor().as_component().0).access_rules_chain().remove(0);
+            self.internal_admin_badge.authorize(|| {
+                access_rules.set_method_auth("withdraw_funds", rule!(require_n_of(threshold, signer_ids)));
+                access_rules.lock_method_auth("withdraw_funds");
+            });
+
+            signer_badges
+        }
+
+        // Makes the seller badge recoverable by a number of recovery guardians, whose badges are
+        // returned to be handed out to trusted parties. The proofs of a threshold of guardians in
+        // the same transaction can then replace a lost or compromised seller badge through
+        // `recover_seller_badge`.
+        pub fn configure_recovery(&mut self, guardians: u8, threshold: u8) -> Bucket {
+            assert!(
+                self.recovery_guardian_badge.is_none(),
+                "The recovery of the seller badge is already configured"
+            );
+            assert!(
+                threshold > 0 && threshold <= guardians,
+                "The threshold must be between one and the number of guardians"
+            );
+
+            let recovery_guardian_badge: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Recovery Guardian Badge")
+                .mintable(rule!(require(self.internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+            self.recovery_guardian_badge = Some(recovery_guardian_badge);
+
+            let mut guardian_badges: Bucket = Bucket::new(recovery_guardian_badge);
+            let mut guardian_ids: Vec<NonFungibleGlobalId> = Vec::new();
+            for guardian in 1..=guardians {
+                let guardian_id: NonFungibleLocalId = NonFungibleLocalId::Integer(guardian as u64);
+                guardian_badges.put(self.internal_admin_badge.authorize(|| {
+                    borrow_resource_manager!(recovery_guardian_badge).mint_non_fungible(&guardian_id, RecoveryGuardian {})
+                }));
+                guardian_ids.push(NonFungibleGlobalId::new(recovery_guardian_badge, guardian_id));
+            }
+
+            // Allowing the guardians to recover the seller badge and locking the rule in place
+            let mut access_rules: ComponentAccessRules =
+                borrow_component!(Runtime::actor().as_component().0).access_rules_chain().remove(0);
+            self.internal_admin_badge.authorize(|| {
+                access_rules.set_method_auth("recover_seller_badge", rule!(require_n_of(threshold, guardian_ids)));
+                access_rules.lock_method_auth("recover_seller_badge");
+            });
+
+            guardian_badges
+        }
+
+        // Replaces the seller badge with a new one, revoking the current seller badge. This lets
+        // the owner rotate the seller badge, for instance when handing the sale over to someone
+        // else.
+        pub fn rotate_seller_badge(&mut self) -> Bucket {
+            self.replace_seller_badge()
+        }
+
+        // Replaces a lost or compromised seller badge with a new one, revoking the current seller
+        // badge. Only the recovery guardians can recover the seller badge. As whoever held the
+        // compromised seller badge could have minted role badges of their own, the role badges are
+        // replaced as well, and the roles have to be handed out again with the new seller badge.
+        pub fn recover_seller_badge(&mut self) -> Bucket {
+            let seller_badge: Bucket = self.replace_seller_badge();
+            self.replace_role_badges();
+            seller_badge
+        }
+
+        // Mints a new badge of the given role. Role badges can also be minted, burned and recalled
+        // by the seller badge directly through their resource.
+        pub fn mint_role_badge(&mut self, role: Role) -> Bucket {
+            let role_badge: ResourceAddress = match role {
+                Role::PriceManager => self.price_manager_badge,
+                Role::Treasurer => self.treasurer_badge,
+                Role::Pauser => self.pauser_badge
+            };
+            self.internal_admin_badge.authorize(|| borrow_resource_manager!(role_badge).mint(1))
+        }
+
+        // Starts a referral program, where `reward_share` of each referred purchase is paid to the
+        // referrer, either as bonus tokens on top of the tokens bought or as a commission out of
+        // the payment.
+        pub fn configure_referrals(&mut self, reward_share: Decimal, reward: ReferralReward) {
+            assert!(
+                reward_share > Decimal::zero() && reward_share < Decimal::one(),
+                "The reward share must be between 0 and 1"
+            );
+            // Bonus tokens count towards the soft cap without being refundable, and commissions are
+            // paid out of the proceeds which would be refunded
+            assert!(
+                self.soft_cap.is_none(),
+                "Referral rewards can not be combined with a soft cap"
+            );
+            assert!(
+                !(matches!(reward, ReferralReward::Commission) && self.beneficiary_badge.is_some()),
+                "Commissions can not be combined with beneficiaries"
+            );
+            assert!(
+                !(matches!(reward, ReferralReward::Commission) && self.liquidity_seeding.is_some()),
+                "Commissions can not be combined with liquidity seeding"
+            );
+            assert!(
+                !(matches!(reward, ReferralReward::Commission) && self.is_sell_back_enabled()),
+                "Commissions can not be combined with selling back"
+            );
+            self.referral_program = Some((reward_share, reward));
+        }
+
+        // Registers a new referrer, returning the referrer badge whose id is the referral code. Only
+        // the seller registers referrers, which limits the rewards to the referrers they trust.
+        // As buyers are anonymous, this does not keep a registered referrer from passing their own
+        // referral code on their own purchases, in effect buying at a discount of the reward share.
+        pub fn register_referrer(&mut self) -> Bucket {
+            self.referrers_registered += 1;
+            let referrer_id: NonFungibleLocalId = NonFungibleLocalId::Integer(self.referrers_registered);
+            self.internal_admin_badge.authorize(|| {
+                borrow_resource_manager!(self.referrer_badge).mint_non_fungible(&referrer_id, Referrer {})
+            })
+        }
+
+        // Claims the rewards owed to a referrer.
+        pub fn claim_referral_rewards(&mut self, referrer_proof: Proof) -> Vec<Bucket> {
+            let referrer_id: NonFungibleLocalId = referrer_proof
+                .validate_proof(ProofValidationMode::ValidateResourceAddress(self.referrer_badge))
+                .expect("Invalid referrer badge provided")
+                .non_fungible::<Referrer>()
+                .local_id()
+                .clone();
+
+            self.referral_rewards
+                .remove(&referrer_id)
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(resource_address, amount)| {
+                    self.referral_rewards_vaults.get_mut(&resource_address).unwrap().take(amount)
+                })
+                .collect()
+        }
+
+        // Pays the referrer with the given referrer badge id their reward for a purchase, which is
+        // kept in the sale until they claim it.
+        fn reward_referrer(
+            &mut self,
+            referrer_id: u64,
+            tokens_bought: Decimal,
+            payment_resource: ResourceAddress,
+            amount_paid: Decimal
+        ) {
+            let (reward_share, reward): (Decimal, ReferralReward) =
+                self.referral_program.expect("The sale has no referral program");
+            assert!(
+                referrer_id > 0 && referrer_id <= self.referrers_registered,
+                "No referrer is registered with the referral code {}",
+                referrer_id
+            );
+
+            let reward: Bucket = match reward {
+                ReferralReward::BonusTokens => {
+                    let bonus_tokens: Decimal = (tokens_bought * reward_share).round(
+                        Self::divisibility(self.sale_tokens_vault.resource_address()),
+                        RoundingMode::TowardsZero
+                    );
+                    assert!(
+                        bonus_tokens <= self.remaining_supply(),
+                        "Insufficient supply for the referral bonus"
+                    );
+                    self.take_sale_tokens(bonus_tokens)
+                }
+                ReferralReward::Commission => {
+                    let commission: Decimal = (amount_paid * reward_share)
+                        .round(Self::divisibility(payment_resource), RoundingMode::TowardsZero);
+                    self.payment_vaults.get_mut(&payment_resource).unwrap().take(commission)
+                }
+            };
+
+            *self.referral_rewards
+                .entry(NonFungibleLocalId::Integer(referrer_id))
+                .or_insert(HashMap::new())
+                .entry(reward.resource_address())
+                .or_insert(Decimal::zero()) += reward.amount();
+            self.referral_rewards_vaults
+                .entry(reward.resource_address())
+                .or_insert(Vault::new(reward.resource_address()))
+                .put(reward);
+        }
+
+        // Splits every payment received from now on between a number of beneficiaries, each getting
+        // the share of the payment at the same position in `shares`. The beneficiary badges are
+        // returned to be handed out to the beneficiaries, who withdraw their shares through
+        // `withdraw_beneficiary_funds`. Whatever is left once the shares are taken stays in the
+        // sale for the treasurer to withdraw.
+        pub fn configure_beneficiaries(&mut self, shares: Vec<Decimal>) -> Bucket {
+            assert!(self.beneficiary_badge.is_none(), "The beneficiaries are already configured");
+            assert!(!shares.is_empty(), "At least one beneficiary must be given");
+            assert!(
+                shares.iter().all(|share| *share > Decimal::zero()),
+                "The share of every beneficiary must be positive"
+            );
+            assert!(
+                shares.iter().fold(self.liquidity_proceeds_share(), |total, share| total + *share) <= Decimal::one(),
+                "The shares of the beneficiaries and of the liquidity must not add up to more than 1"
+            );
+            assert!(self.soft_cap.is_none(), "Beneficiaries can not be combined with a soft cap");
+            assert!(
+                !matches!(self.referral_program, Some((_, ReferralReward::Commission))),
+                "Beneficiaries can not be combined with commissions"
+            );
+            assert!(!self.is_sell_back_enabled(), "Beneficiaries can not be combined with selling back");
+
+            let beneficiary_badge: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", "Beneficiary Badge")
+                .mintable(rule!(require(self.internal_admin_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+            self.beneficiary_badge = Some(beneficiary_badge);
+
+            let mut beneficiary_badges: Bucket = Bucket::new(beneficiary_badge);
+            for (index, share) in shares.into_iter().enumerate() {
+                let beneficiary_id: NonFungibleLocalId = NonFungibleLocalId::Integer(index as u64 + 1);
+                beneficiary_badges.put(self.internal_admin_badge.authorize(|| {
+                    borrow_resource_manager!(beneficiary_badge)
+                        .mint_non_fungible(&beneficiary_id, Beneficiary { share: share })
+                }));
+                self.beneficiary_shares.insert(beneficiary_id.clone(), share);
+                self.beneficiary_vaults.insert(beneficiary_id, HashMap::new());
+            }
+
+            beneficiary_badges
+        }
+
+        // Withdraws everything which was paid to a beneficiary.
+        pub fn withdraw_beneficiary_funds(&mut self, beneficiary_proof: Proof) -> Vec<Bucket> {
+            let beneficiary_id: NonFungibleLocalId = beneficiary_proof
+                .validate_proof(ProofValidationMode::ValidateResourceAddress(
+                    self.beneficiary_badge.expect("The sale has no beneficiaries")
+                ))
+                .expect("Invalid beneficiary badge provided")
+                .non_fungible::<Beneficiary>()
+                .local_id()
+                .clone();
+
+            self.beneficiary_vaults
+                .get_mut(&beneficiary_id)
+                .unwrap()
+                .values_mut()
+                .map(|vault| vault.take_all())
+                .collect()
+        }
+
+        // Puts a payment into the sale, splitting it between the beneficiaries if there are any.
+        // The shares are rounded down, leaving any dust in the payment vault.
+        fn receive_payment(&mut self, mut payment: Bucket) {
+            let payment_resource: ResourceAddress = payment.resource_address();
+            let amount_paid: Decimal = payment.amount();
+            let divisibility: u32 = Self::divisibility(payment_resource);
+            for (beneficiary_id, share) in self.beneficiary_shares.iter() {
+                let amount: Decimal = (amount_paid * *share).round(divisibility, RoundingMode::TowardsZero);
+                self.beneficiary_vaults
+                    .get_mut(beneficiary_id)
+                    .unwrap()
+                    .entry(payment_resource)
+                    .or_insert(Vault::new(payment_resource))
+                    .put(payment.take(amount));
+            }
+            if let (Some(liquidity_seeding), Some(liquidity_proceeds_vault)) =
+                (&self.liquidity_seeding, &mut self.liquidity_proceeds_vault)
+            {
+                if liquidity_seeding.payment_resource == payment_resource {
+                    let amount: Decimal = (amount_paid * liquidity_seeding.proceeds_share)
+                        .round(divisibility, RoundingMode::TowardsZero);
+                    liquidity_proceeds_vault.put(payment.take(amount));
+                }
+            }
+            self.payment_vaults.get_mut(&payment_resource).unwrap().put(payment);
+        }
+
+        // Seeds the liquidity of a Radiswap pool once the sale is finalized. Before the sale
+        // starts, `tokens_share` of its supply is reserved for the pool, and `proceeds_share` of
+        // each payment in the given payment resource is then reserved for the pool as well. The
+        // pool is instantiated from the Radiswap blueprint of this package with the given fee,
+        // and its pool shares stay locked in the sale for `lock_epochs` epochs.
+        pub fn configure_liquidity_seeding(
+            &mut self,
+            payment_resource: ResourceAddress,
+            proceeds_share: Decimal,
+            tokens_share: Decimal,
+            fee: Decimal,
+            lock_epochs: u64
+        ) {
+            assert!(self.liquidity_seeding.is_none(), "The liquidity seeding is already configured");
+            assert!(
+                Runtime::current_epoch() < self.start_epoch,
+                "The liquidity seeding can only be configured before the sale starts"
+            );
+            assert!(
+                self.payment_vaults.contains_key(&payment_resource),
+                "The given resource is not an accepted payment resource"
+            );
+            assert!(
+                proceeds_share > Decimal::zero() && tokens_share > Decimal::zero() && tokens_share < Decimal::one(),
+                "The shares of the proceeds and tokens must be positive and leave tokens to sell"
+            );
+            assert!(
+                self.beneficiary_shares.values().fold(proceeds_share, |total, share| total + *share) <= Decimal::one(),
+                "The shares of the beneficiaries and of the liquidity must not add up to more than 1"
+            );
+            assert!(fee >= Decimal::zero() && fee <= Decimal::one(), "The fee must be between 0 and 1");
+            assert!(self.soft_cap.is_none(), "Liquidity seeding can not be combined with a soft cap");
+            assert!(
+                !matches!(self.referral_program, Some((_, ReferralReward::Commission))),
+                "Liquidity seeding can not be combined with commissions"
+            );
+            assert!(!self.is_sell_back_enabled(), "Liquidity seeding can not be combined with selling back");
+
+            // Reserving the tokens for the pool, which are no longer for sale
+            let reserved_tokens: Decimal = (self.supply_cap * tokens_share).round(
+                Self::divisibility(self.sale_tokens_vault.resource_address()),
+                RoundingMode::TowardsZero
+            );
+            self.supply_cap -= reserved_tokens;
+            let tokens: Bucket = match &self.minter_badge_vault {
+                Some(minter_badge_vault) => minter_badge_vault.authorize(|| {
+                    borrow_resource_manager!(self.sale_tokens_vault.resource_address()).mint(reserved_tokens)
+                }),
+                None => self.sale_tokens_vault.take(reserved_tokens)
+            };
+            self.liquidity_tokens_vault.put(tokens);
+
+            self.liquidity_proceeds_vault = Some(Vault::new(payment_resource));
+            self.liquidity_seeding = Some(LiquiditySeeding {
+                payment_resource: payment_resource,
+                proceeds_share: proceeds_share,
+                fee: fee,
+                lock_epochs: lock_epochs
+            });
+        }
+
+        // Finalizes a sale which has ended by instantiating a Radiswap pool with the tokens and
+        // proceeds reserved for it, returning the address of the pool. Anyone can finalize the
+        // sale, and the pool shares stay locked in the sale until they unlock.
+        pub fn finalize(&mut self) -> ComponentAddress {
+            let liquidity_seeding: LiquiditySeeding =
+                self.liquidity_seeding.clone().expect("The sale does not seed liquidity");
+            assert!(self.has_ended(), "The sale can only be finalized once it ends");
+            assert!(self.liquidity_pool.is_none(), "The sale has already been finalized");
+
+            let proceeds: Bucket = self.liquidity_proceeds_vault.as_mut().unwrap().take_all();
+            assert!(!proceeds.is_empty(), "No proceeds were raised to seed the liquidity with");
+            let (pool, pool_shares): (ComponentAddress, Bucket) = Runtime::call_function(
+                Runtime::package_address(),
+                "Radiswap",
+                "instantiate_pool",
+                args!(
+                    self.liquidity_tokens_vault.take_all(),
+                    proceeds,
+                    Decimal::from(POOL_SHARES_INITIAL_SUPPLY),
+                    liquidity_seeding.fee
+                )
+            );
+
+            self.liquidity_pool = Some((pool, Runtime::current_epoch() + liquidity_seeding.lock_epochs));
+            self.liquidity_shares_vault = Some(Vault::with_bucket(pool_shares));
+            pool
+        }
+
+        // Withdraws the pool shares of the pool seeded when the sale was finalized, once they
+        // unlock.
+        pub fn withdraw_liquidity_shares(&mut self) -> Bucket {
+            let (_, unlock_epoch): (ComponentAddress, u64) =
+                self.liquidity_pool.expect("The sale has not been finalized");
+            assert!(
+                Runtime::current_epoch() >= unlock_epoch,
+                "The pool shares are locked until epoch {}",
+                unlock_epoch
+            );
+            self.liquidity_shares_vault.as_mut().unwrap().take_all()
+        }
+
+        // Gets the share of each payment reserved for the liquidity, which is zero when the sale
+        // does not seed liquidity.
+        fn liquidity_proceeds_share(&self) -> Decimal {
+            match &self.liquidity_seeding {
+                Some(liquidity_seeding) => liquidity_seeding.proceeds_share,
+                None => Decimal::zero()
+            }
+        }
+
+        // Sets a soft cap on the sale, the minimum amount of tokens which must be sold for the sale
+        // to succeed. The proceeds are then locked until the sale ends, after which they can be
+        // withdrawn if the soft cap was reached, or are refunded to the buyers otherwise.
+        pub fn configure_soft_cap(&mut self, soft_cap: Decimal) {
+            assert!(
+                Runtime::current_epoch() < self.start_epoch,
+                "The soft cap can only be configured before the sale starts"
+            );
+            assert!(
+                self.vesting_schedule.is_none(),
+                "A soft cap can not be combined with vesting"
+            );
+            assert!(
+                self.referral_program.is_none(),
+                "A soft cap can not be combined with referral rewards"
+            );
+            assert!(
+                self.beneficiary_badge.is_none(),
+                "A soft cap can not be combined with beneficiaries"
+            );
+            assert!(
+                self.liquidity_seeding.is_none(),
+                "A soft cap can not be combined with liquidity seeding"
+            );
+            assert!(!self.is_sell_back_enabled(), "A soft cap can not be combined with selling back");
+            assert!(
+                soft_cap > Decimal::zero() && soft_cap <= self.supply_cap,
+                "The soft cap must be positive and within the supply of the sale"
+            );
+            self.soft_cap = Some(soft_cap);
+        }
+
+        // Refunds a buyer of a sale which did not reach its soft cap. The buyer returns their
+        // receipt along with the tokens recorded on it, and gets back everything that they paid.
+        pub fn refund(&mut self, receipt: Bucket, tokens: Bucket) -> Vec<Bucket> {
+            let soft_cap: Decimal = self.soft_cap.expect("The sale has no soft cap");
+            assert!(self.has_ended(), "Refunds are only possible once the sale ends");
+            assert!(
+                self.tokens_sold < soft_cap,
+                "The soft cap was reached, so no refunds are possible"
+            );
+            assert!(
+                receipt.resource_address() == self.buyer_receipt_resource,
+                "Invalid buyer receipt provided"
+            );
+            assert!(
+                tokens.resource_address() == self.sale_tokens_vault.resource_address(),
+                "The tokens returned are not the ones sold"
+            );
+
+            let receipt_data: BuyerReceipt = receipt.non_fungible::<BuyerReceipt>().data();
+            assert!(
+                tokens.amount() == receipt_data.tokens_bought,
+                "The {} tokens bought with this receipt must be returned",
+                receipt_data.tokens_bought
+            );
+
+            self.tokens_sold -= tokens.amount();
+            self.sale_tokens_vault.put(tokens);
+            self.internal_admin_badge.authorize(|| receipt.burn());
+
+            receipt_data
+                .amounts_paid
+                .into_iter()
+                .map(|(payment_resource, amount_paid)| {
+                    *self.total_raised.get_mut(&payment_resource).unwrap() -= amount_paid;
+                    self.payment_vaults.get_mut(&payment_resource).unwrap().take(amount_paid)
+                })
+                .collect()
+        }
+
+        // Makes the tokens bought from now on vest instead of being delivered. Buyers then get a
+        // vesting NFT instead of the tokens, and can claim nothing until `cliff_epochs` epochs
+        // after their purchase, after which the tokens vest linearly until they are fully vested
+        // `vesting_epochs` epochs after the purchase.
+        pub fn configure_vesting(&mut self, cliff_epochs: u64, vesting_epochs: u64) {
+            assert!(
+                Runtime::current_epoch() < self.start_epoch,
+                "Vesting can only be configured before the sale starts"
+            );
+            assert!(self.soft_cap.is_none(), "Vesting can not be combined with a soft cap");
+            assert!(vesting_epochs > 0, "The vesting period must be at least one epoch");
+            assert!(cliff_epochs <= vesting_epochs, "The cliff must not be after the end of the vesting");
+            self.vesting_schedule = Some((cliff_epochs, vesting_epochs));
+        }
+
+        // Claims the tokens of a vesting NFT which have vested and were not claimed yet.
+        pub fn claim(&mut self, vesting_proof: Proof) -> Bucket {
+            let vesting_nft: NonFungible<VestingSchedule> = vesting_proof
+                .validate_proof(ProofValidationMode::ValidateResourceAddress(self.vesting_resource))
+                .expect("Invalid vesting NFT provided")
+                .non_fungible::<VestingSchedule>();
+            let mut vesting_schedule: VestingSchedule = vesting_nft.data();
+
+            let current_epoch: u64 = Runtime::current_epoch();
+            assert!(
+                current_epoch >= vesting_schedule.cliff_epoch,
+                "Nothing can be claimed before the cliff at epoch {}",
+                vesting_schedule.cliff_epoch
+            );
+            let elapsed_epochs: u64 = current_epoch - vesting_schedule.start_epoch;
+            let vested_amount: Decimal = if elapsed_epochs >= vesting_schedule.vesting_duration {
+                vesting_schedule.total_allocation
+            } else {
+                (vesting_schedule.total_allocation * Decimal::from(elapsed_epochs)
+                    / Decimal::from(vesting_schedule.vesting_duration))
+                .round(Self::divisibility(self.vesting_vault.resource_address()), RoundingMode::TowardsZero)
+            };
+
+            let claimable_amount: Decimal = vested_amount - vesting_schedule.claimed;
+            vesting_schedule.claimed = vested_amount;
+            self.internal_admin_badge.authorize(|| {
+                borrow_resource_manager!(self.vesting_resource)
+                    .update_non_fungible_data(vesting_nft.local_id(), vesting_schedule)
+            });
+            self.vesting_vault.take(claimable_amount)
+        }
+
+        // Delivers the purchased tokens to the buyer, or when the tokens vest, locks them in the
+        // vesting vault and delivers a vesting NFT for them instead.
+        fn deliver(&mut self, tokens: Bucket) -> Bucket {
+            match self.vesting_schedule {
+                Some((cliff_epochs, vesting_epochs)) => {
+                    let current_epoch: u64 = Runtime::current_epoch();
+                    let vesting_schedule: VestingSchedule = VestingSchedule {
+                        total_allocation: tokens.amount(),
+                        claimed: Decimal::zero(),
+                        start_epoch: current_epoch,
+                        cliff_epoch: current_epoch + cliff_epochs,
+                        vesting_duration: vesting_epochs
+                    };
+                    self.vesting_vault.put(tokens);
+
+                    self.vesting_nfts_minted += 1;
+                    let vesting_nft_id: NonFungibleLocalId = NonFungibleLocalId::Integer(self.vesting_nfts_minted);
+                    self.internal_admin_badge.authorize(|| {
+                        borrow_resource_manager!(self.vesting_resource)
+                            .mint_non_fungible(&vesting_nft_id, vesting_schedule)
+                    })
+                }
+                None => tokens
+            }
+        }
+
+        // Mints a new buyer receipt recording a first purchase of the given amount of tokens for the
+        // given amount of the payment resource.
+        fn issue_receipt(
+            &mut self,
+            tokens_bought: Decimal,
+            payment_resource: ResourceAddress,
+            amount_paid: Decimal
+        ) -> Bucket {
+            self.assert_within_purchase_limits(tokens_bought);
+
+            let mut amounts_paid: HashMap<ResourceAddress, Decimal> = HashMap::new();
+            amounts_paid.insert(payment_resource, amount_paid);
+            let (receipt_id, receipt): (NonFungibleLocalId, Bucket) = self.mint_receipt(BuyerReceipt {
+                tokens_bought: tokens_bought,
+                amounts_paid: amounts_paid
+            });
+            self.record_purchase(&receipt_id, true, tokens_bought, payment_resource, amount_paid);
+            receipt
+        }
+
+        // Mints a new buyer receipt with the given data, returning it along with its id.
+        fn mint_receipt(&mut self, receipt_data: BuyerReceipt) -> (NonFungibleLocalId, Bucket) {
+            self.buyer_receipts_minted += 1;
+            let receipt_id: NonFungibleLocalId = NonFungibleLocalId::Integer(self.buyer_receipts_minted);
+            let receipt: Bucket = self.internal_admin_badge.authorize(|| {
+                borrow_resource_manager!(self.buyer_receipt_resource).mint_non_fungible(&receipt_id, receipt_data)
+            });
+            (receipt_id, receipt)
+        }
+
+        // Records a purchase of the given amount of tokens for the given amount of the payment
+        // resource on an existing buyer receipt.
+        fn update_receipt(
+            &mut self,
+            receipt_proof: Proof,
+            tokens_bought: Decimal,
+            payment_resource: ResourceAddress,
+            amount_paid: Decimal
+        ) {
+            let receipt: NonFungible<BuyerReceipt> = receipt_proof
+                .validate_proof(ProofValidationMode::ValidateResourceAddress(self.buyer_receipt_resource))
+                .expect("Invalid buyer receipt provided")
+                .non_fungible::<BuyerReceipt>();
+
+            // The receipts of registered buyers are minted before their first purchase
+            let mut receipt_data: BuyerReceipt = receipt.data();
+            let is_first_purchase: bool = receipt_data.amounts_paid.is_empty();
+            receipt_data.tokens_bought += tokens_bought;
+            *receipt_data.amounts_paid.entry(payment_resource).or_insert(Decimal::zero()) += amount_paid;
+
+            // While the purchases are capped, the presale purchases of the allowlist badge with
+            // which the buyer registered count towards the limits as well
+            let mut total_tokens_bought: Decimal = receipt_data.tokens_bought;
+            if self.max_purchase < Decimal::MAX {
+                let allowlist_id: &NonFungibleLocalId = self
+                    .registered_buyers
+                    .get(receipt.local_id())
+                    .expect("The purchases are capped, so only the receipts of registered buyers are accepted");
+                total_tokens_bought += *self.presale_purchases.get(allowlist_id).unwrap_or(&Decimal::zero());
+            }
+            self.assert_within_purchase_limits(total_tokens_bought);
+            self.record_purchase(
+                receipt.local_id(),
+                is_first_purchase,
+                tokens_bought,
+                payment_resource,
+                amount_paid
+            );
+
+            self.internal_admin_badge.authorize(|| {
+                borrow_resource_manager!(self.buyer_receipt_resource)
+                    .update_non_fungible_data(receipt.local_id(), receipt_data)
+            });
+        }
+
+        // Adds a purchase to the statistics of the sale, counting a new buyer for the first purchase
+        // made with a receipt, and emits a purchase event for it. As components can not emit
+        // structured events yet, the event is emitted as a log message.
+        fn record_purchase(
+            &mut self,
+            receipt_id: &NonFungibleLocalId,
+            is_first_purchase: bool,
+            tokens_bought: Decimal,
+            payment_resource: ResourceAddress,
+            amount_paid: Decimal
+        ) {
+            *self.total_raised.entry(payment_resource).or_insert(Decimal::zero()) += amount_paid;
+            if is_first_purchase {
+                self.buyer_count += 1;
+            }
+            info!(
+                "[PurchaseEvent] receipt id: {:?}, amount paid: {} of {:?}, tokens received: {}",
+                receipt_id,
+                amount_paid,
+                payment_resource,
+                tokens_bought
+            );
+        }
+
+        // Asserts that anyone can buy without registering, which is only the case while the
+        // purchases are not capped.
+        fn assert_purchases_uncapped(&self) {
+            assert!(
+                self.max_purchase == Decimal::MAX,
+                "The purchases are capped, register with an allowlist badge through register_buyer and buy through buy_with_receipt"
+            );
+        }
+
+        fn assert_within_purchase_limits(&self, total_tokens_bought: Decimal) {
+            assert!(
+                total_tokens_bought >= self.min_purchase,
+                "Each buyer must buy at least {} tokens",
+                self.min_purchase
+            );
+            assert!(
+                total_tokens_bought <= self.max_purchase,
+                "Each buyer can buy at most {} tokens",
+                self.max_purchase
+            );
+        }
+
+        // Whether the sale has ended, either because its end epoch has passed or because it was
+        // closed.
+        fn has_ended(&self) -> bool {
+            self.is_closed || Runtime::current_epoch() > self.end_epoch
+        }
+
+        fn assert_sale_open(&self) {
+            let current_epoch: u64 = Runtime::current_epoch();
+            assert!(!self.is_closed, "The sale has been closed");
+            assert!(!self.is_paused, "The sale is paused");
+            assert!(
+                current_epoch >= self.start_epoch,
+                "The sale only starts at epoch {}",
+                self.start_epoch
+            );
+            assert!(
+                current_epoch <= self.end_epoch,
+                "The sale ended at epoch {}",
+                self.end_epoch
+            );
+        }
+
+        fn assert_public_sale(&self) {
+            assert!(
+                Runtime::current_epoch() >= self.public_sale_epoch,
+                "The public sale only starts at epoch {}, use presale_buy with an allowlist badge",
+                self.public_sale_epoch
+            );
+        }
+
+        // Gets the pricing of the tokens in the given payment resource, panicking if the resource
+        // is not accepted as payment by this sale.
+        fn pricing(&self, payment_resource: ResourceAddress) -> &Pricing {
+            self.pricing
+                .get(&payment_resource)
+                .expect("The given resource is not an accepted payment resource")
+        }
+
+        // Replaces the pricing of the tokens in an already accepted payment resource. A bonding
+        // curve along which tokens can be sold back can not be replaced once tokens were sold along
+        // it, as their holders could no longer sell them back.
+        fn set_pricing(&mut self, payment_resource: ResourceAddress, pricing: Pricing) {
+            pricing.validate();
+            assert!(
+                self.pricing.contains_key(&payment_resource),
+                "The given resource is not an accepted payment resource"
+            );
+            assert!(
+                self.curve_tokens_sold(payment_resource).is_zero(),
+                "Tokens were sold along a bonding curve with selling back enabled, so its pricing can not be replaced"
+            );
+            self.pricing.insert(payment_resource, pricing);
+        }
+
+        // Whether tokens can be sold back along the bonding curve of any of the payment resources.
+        fn is_sell_back_enabled(&self) -> bool {
+            self.pricing.values().any(|pricing| {
+                matches!(
+                    pricing,
+                    Pricing::BondingCurve {
+                        sell_back_enabled: true,
+                        ..
+                    }
+                )
+            })
+        }
+
+        // Gets the amount of tokens sold on which the pricing of the given payment resource is based.
+        // A bonding curve along which tokens can be sold back is priced along the tokens sold on
+        // it alone, so that the sales in other payment resources and the referral bonus tokens do
+        // not move its price away from the reserve backing it.
+        fn pricing_position(&self, payment_resource: ResourceAddress) -> Decimal {
+            match self.pricing(payment_resource) {
+                Pricing::BondingCurve {
+                    sell_back_enabled: true,
+                    ..
+                } => self.curve_tokens_sold(payment_resource),
+                _ => self.tokens_sold
+            }
+        }
+
+        // Gets the amount of tokens sold along the bonding curve of the given payment resource,
+        // net of the tokens sold back.
+        fn curve_tokens_sold(&self, payment_resource: ResourceAddress) -> Decimal {
+            *self.curve_tokens_sold.get(&payment_resource).unwrap_or(&Decimal::zero())
+        }
+
+        // Records the given amount of tokens as sold along the bonding curve of the given payment
+        // resource when they can be sold back along it.
+        fn record_curve_sale(&mut self, payment_resource: ResourceAddress, amount: Decimal) {
+            if let Pricing::BondingCurve {
+                sell_back_enabled: true,
+                ..
+            } = self.pricing(payment_resource)
+            {
+                *self.curve_tokens_sold.entry(payment_resource).or_insert(Decimal::zero()) += amount;
+            }
+        }
+
+        // Takes the given amount of sold tokens out of the sale. Mint-on-demand sales first take the
+        // tokens which were sold back to them and mint the rest.
+        fn take_sale_tokens(&mut self, amount: Decimal) -> Bucket {
+            self.tokens_sold += amount;
+            match &self.minter_badge_vault {
+                Some(minter_badge_vault) if amount > self.sale_tokens_vault.amount() => {
+                    let mut tokens: Bucket = self.sale_tokens_vault.take_all();
+                    let amount_to_mint: Decimal = amount - tokens.amount();
+                    tokens.put(minter_badge_vault.authorize(|| {
+                        borrow_resource_manager!(self.sale_tokens_vault.resource_address()).mint(amount_to_mint)
+                    }));
+                    tokens
+                }
+                _ => self.sale_tokens_vault.take(amount)
+            }
+        }
+
+        // Creates a new seller badge and makes it the only one accepted by the seller methods and
+        // the role badge resources, revoking the current seller badge.
+        fn replace_seller_badge(&mut self) -> Bucket {
+            let seller_badge: Bucket = ResourceBuilder::new_fungible()
+                .metadata("name", "Seller Badge")
+                .metadata("symbol", "SELLER")
+                .mint_initial_supply(1);
+            self.seller_badge = seller_badge.resource_address();
+
+            let mut access_rules: ComponentAccessRules =
+                borrow_component!(Runtime::actor().as_component().0).access_rules_chain().remove(0);
+            let role_badges: [ResourceAddress; 3] = [self.price_manager_badge, self.treasurer_badge, self.pauser_badge];
+            let new_seller_badge: ResourceAddress = self.seller_badge;
+            let internal_admin_badge: ResourceAddress = self.internal_admin_badge.resource_address();
+            self.internal_admin_badge.authorize(|| {
+                for method in SELLER_METHODS {
+                    access_rules.set_method_auth(method, rule!(require(new_seller_badge)));
+                }
+                for role_badge in role_badges {
+                    let mut role_badge_manager: ResourceManager = borrow_resource_manager!(role_badge);
+                    role_badge_manager.set_mintable(rule!(require(new_seller_badge) || require(internal_admin_badge)));
+                    role_badge_manager.set_burnable(rule!(require(new_seller_badge)));
+                    role_badge_manager.set_recallable(rule!(require(new_seller_badge)));
+                }
+            });
+
+            seller_badge
+        }
+
+        // Replaces the resources of the role badges with new ones, so that none of the role badges
+        // minted so far are accepted anymore. The withdrawals are left alone once they require the
+        // treasury signers instead of a treasurer badge.
+        fn replace_role_badges(&mut self) {
+            let seller_badge: ResourceAddress = self.seller_badge;
+            let internal_admin_badge: ResourceAddress = self.internal_admin_badge.resource_address();
+            self.price_manager_badge = Self::create_role_badge("Price Manager Badge", seller_badge, internal_admin_badge);
+            self.treasurer_badge = Self::create_role_badge("Treasurer Badge", seller_badge, internal_admin_badge);
+            self.pauser_badge = Self::create_role_badge("Pauser Badge", seller_badge, internal_admin_badge);
+
+            let mut access_rules: ComponentAccessRules =
+                borrow_component!(Runtime::actor().as_component().0).access_rules_chain().remove(0);
+            let price_manager_badge: ResourceAddress = self.price_manager_badge;
+            let treasurer_badge: ResourceAddress = self.treasurer_badge;
+            let pauser_badge: ResourceAddress = self.pauser_badge;
+            let multisig_withdrawals: bool = self.treasury_signer_badge.is_some();
+            self.internal_admin_badge.authorize(|| {
+                for method in PRICE_MANAGER_METHODS {
+                    access_rules.set_method_auth(method, rule!(require(price_manager_badge)));
+                }
+                for method in PAUSER_METHODS {
+                    access_rules.set_method_auth(method, rule!(require(pauser_badge)));
+                }
+                if !multisig_withdrawals {
+                    access_rules.set_method_auth("withdraw_funds", rule!(require(treasurer_badge)));
+                }
+            });
+        }
+
+        // Creates the resource of a role badge, which can be minted by the component or the seller
+        // badge, and burned or recalled by the seller badge. These rules can be changed by the
+        // component so that they follow the seller badge when it is replaced.
+        fn create_role_badge(
+            name: &str,
+            seller_badge: ResourceAddress,
+            internal_admin_badge: ResourceAddress
+        ) -> ResourceAddress {
+            ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", name)
+                .mintable(
+                    rule!(require(seller_badge) || require(internal_admin_badge)),
+                    MUTABLE(rule!(require(internal_admin_badge)))
+                )
+                .burnable(rule!(require(seller_badge)), MUTABLE(rule!(require(internal_admin_badge))))
+                .recallable(rule!(require(seller_badge)), MUTABLE(rule!(require(internal_admin_badge))))
+                .create_with_no_initial_supply()
+        }
+
+        // Gets the divisibility of the given resource as the number of decimal places to round its
+        // amounts to, non-fungible resources being indivisible.
+        fn divisibility(resource_address: ResourceAddress) -> u32 {
+            match borrow_resource_manager!(resource_address).resource_type() {
+                ResourceType::Fungible { divisibility } => u32::from(divisibility),
+                ResourceType::NonFungible { .. } => 0
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+// The phases which the sale goes through.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone, Copy)]
+pub enum SalePhase {
+    // The sale has not started yet.
+    NotStarted,
+
+    // Only the holders of allowlist badges can buy tokens.
+    Presale,
+
+    // Anyone can buy tokens.
+    PublicSale,
+
+    // The sale has been paused by a pauser and no tokens can be bought until it is resumed.
+    Paused,
+
+    // The end epoch of the sale has passed.
+    Ended,
+
+    // The sale has been closed by its owner.
+    Closed
+}
+
+// The roles which the owner of the sale can hand out, each backed by its own badge.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone, Copy)]
+pub enum Role {
+    // Can change the price of the tokens and their pricing.
+    PriceManager,
+
+    // Can withdraw the proceeds of the sale.
+    Treasurer,
+
+    // Can pause and resume the sale.
+    Pauser
+}
+
+// The ways in which referrers can be rewarded for the purchases which they refer.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone, Copy)]
+pub enum ReferralReward {
+    // The referrer gets bonus tokens on top of the tokens bought.
+    BonusTokens,
+
+    // The referrer gets a commission out of the payment.
+    Commission
+}
+
+// The data of the allowlist badges which allow their holders to take part in the presale.
+#[derive(NonFungibleData)]
+pub struct AllowlistEntry {
+    // The maximum amount of tokens which the holder of the badge can buy in the presale.
+    allocation: Decimal
+}
+
+// The data of the buyer receipts which record the purchases made by their holders.
+#[derive(NonFungibleData)]
+pub struct BuyerReceipt {
+    // The total amount of tokens bought with this receipt.
+    #[mutable]
+    tokens_bought: Decimal,
+
+    // The total amount paid for these tokens in each of the payment resources.
+    #[mutable]
+    amounts_paid: HashMap<ResourceAddress, Decimal>
+}
+
+// The data of the vesting NFTs which entitle their holders to claim the purchased tokens as they
+// vest.
+#[derive(NonFungibleData)]
+pub struct VestingSchedule {
+    // The total amount of tokens which vest.
+    total_allocation: Decimal,
+
+    // The amount of tokens claimed so far.
+    #[mutable]
+    claimed: Decimal,
+
+    // The epoch of the purchase, from which the tokens start vesting.
+    start_epoch: u64,
+
+    // The epoch before which none of the tokens can be claimed.
+    cliff_epoch: u64,
+
+    // The number of epochs after the start epoch until all of the tokens have vested.
+    vesting_duration: u64
+}
+
+// The data of the treasury signer badges, which carry no data beyond their ids.
+#[derive(NonFungibleData)]
+pub struct TreasurySigner {}
+
+// How the liquidity of a pool is seeded with part of the tokens and proceeds of the sale.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone)]
+pub struct LiquiditySeeding {
+    // The payment resource which the tokens are paired with in the pool, and the share of each
+    // payment in that resource which is reserved for the pool.
+    payment_resource: ResourceAddress,
+    proceeds_share: Decimal,
+
+    // The fee of the pool.
+    fee: Decimal,
+
+    // The number of epochs for which the pool shares stay locked after the sale is finalized.
+    lock_epochs: u64
+}
+
+// The data of the beneficiary badges, recording the share of each payment which goes to the
+// beneficiary.
+#[derive(NonFungibleData)]
+pub struct Beneficiary {
+    share: Decimal
+}
+
+// The data of the recovery guardian badges, which carry no data beyond their ids.
+#[derive(NonFungibleData)]
+pub struct RecoveryGuardian {}
+
+// The data of the referrer badges, which carry no data beyond their ids.
+#[derive(NonFungibleData)]
+pub struct Referrer {}
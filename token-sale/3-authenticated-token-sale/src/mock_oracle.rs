@@ -0,0 +1,49 @@
+use scrypto::prelude::*;
+
+// A mock price oracle reporting the price of a single unit of a resource in USD, as expected by the
+// USD pricing of the token sale. Its price is simply set by the holder of its admin badge, making it
+// suitable for testing sales priced in USD but not for real ones.
+#[blueprint]
+mod mock_oracle {
+    struct MockOracle {
+        // The price reported by the oracle along with the epoch at which it was last updated.
+        price: Decimal,
+        last_updated: u64
+    }
+
+    impl MockOracle {
+        pub fn instantiate_mock_oracle(price: Decimal) -> (ComponentAddress, Bucket) {
+            assert!(price > Decimal::zero(), "The price must be positive");
+
+            let admin_badge: Bucket = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "Mock Oracle Admin Badge")
+                .mint_initial_supply(1);
+
+            let access_rules: AccessRules = AccessRules::new()
+                .method("set_price", rule!(require(admin_badge.resource_address())), LOCKED)
+                .default(rule!(allow_all), LOCKED);
+
+            let mut component: MockOracleComponent = Self {
+                price: price,
+                last_updated: Runtime::current_epoch()
+            }
+            .instantiate();
+            component.add_access_check(access_rules);
+
+            (component.globalize(), admin_badge)
+        }
+
+        // Updates the price reported by the oracle, marking it as updated at the current epoch.
+        pub fn set_price(&mut self, price: Decimal) {
+            assert!(price > Decimal::zero(), "The price must be positive");
+            self.price = price;
+            self.last_updated = Runtime::current_epoch();
+        }
+
+        // Gets the price reported by the oracle along with the epoch at which it was last updated.
+        pub fn get_price(&self) -> (Decimal, u64) {
+            (self.price, self.last_updated)
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use scrypto::prelude::*;
+
+// A companion blueprint of the token sale which sells non-fungible items, such as the numbered
+// items of a collection, instead of a fungible token. Each item is sold at the default price of
+// the sale unless it has a price of its own, and buyers can either buy a specific item or a random
+// one. As in the token sale, only the holder of the seller badge can withdraw the proceeds and
+// change the prices.
+#[blueprint]
+mod nft_sale {
+    struct NftSale {
+        // The vault where the items which are still for sale are stored.
+        items_vault: Vault,
+
+        // The vault where the payments are stored.
+        payment_vault: Vault,
+
+        // The price of the items which do not have a price of their own.
+        default_price: Decimal,
+
+        // The prices of the items which are priced differently from the default price.
+        item_prices: HashMap<NonFungibleLocalId, Decimal>
+    }
+
+    impl NftSale {
+        // Creates a new collection with an item for each of the given names and image URLs,
+        // numbered from 1 in the given order, and puts all of its items up for sale.
+        pub fn instantiate_collection_sale(
+            collection_name: String,
+            collection_description: String,
+            items: Vec<(String, String)>,
+            payment_resource: ResourceAddress,
+            price_per_item: Decimal
+        ) -> (ComponentAddress, Bucket) {
+            assert!(!items.is_empty(), "The collection must have at least one item");
+
+            // Creating a badge which can mint the items, and which is burned once all of them are
+            // minted so that the collection can never grow
+            let minter_badge: Bucket = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "Collection Minter Badge")
+                .burnable(rule!(allow_all), LOCKED)
+                .mint_initial_supply(1);
+            let collection_resource: ResourceAddress = ResourceBuilder::new_integer_non_fungible()
+                .metadata("name", collection_name)
+                .metadata("description", collection_description)
+                .mintable(rule!(require(minter_badge.resource_address())), LOCKED)
+                .create_with_no_initial_supply();
+
+            let mut collection: Bucket = Bucket::new(collection_resource);
+            for (index, (name, image_url)) in items.into_iter().enumerate() {
+                let item_id: NonFungibleLocalId = NonFungibleLocalId::Integer(index as u64 + 1);
+                collection.put(minter_badge.authorize(|| {
+                    borrow_resource_manager!(collection_resource).mint_non_fungible(
+                        &item_id,
+                        CollectibleItem {
+                            name: name,
+                            image_url: image_url
+                        }
+                    )
+                }));
+            }
+            minter_badge.burn();
+
+            Self::instantiate_nft_sale(collection, payment_resource, price_per_item)
+        }
+
+        // Puts the given existing non-fungible items up for sale.
+        pub fn instantiate_nft_sale(
+            items: Bucket,
+            payment_resource: ResourceAddress,
+            price_per_item: Decimal
+        ) -> (ComponentAddress, Bucket) {
+            assert!(
+                matches!(
+                    borrow_resource_manager!(items.resource_address()).resource_type(),
+                    ResourceType::NonFungible { .. }
+                ),
+                "Only non-fungible items can be sold"
+            );
+            assert!(!items.is_empty(), "You must pass in the items to sell");
+            assert!(price_per_item > Decimal::zero(), "The price per item must be positive");
+
+            // Creating a new seller badge which makes its holder the owner of the sale
+            let seller_badge: Bucket = ResourceBuilder::new_fungible()
+                .metadata("name", "Seller Badge")
+                .metadata("symbol", "SELLER")
+                .mint_initial_supply(1);
+
+            // Setting the access rules to only allow the seller badge to withdraw the funds and
+            // the unsold items, and to change the prices
+            let access_rules: AccessRules = AccessRules::new()
+                .method("withdraw_funds", rule!(require(seller_badge.resource_address())), LOCKED)
+                .method("withdraw_unsold_items", rule!(require(seller_badge.resource_address())), LOCKED)
+                .method("change_price", rule!(require(seller_badge.resource_address())), LOCKED)
+                .method("set_item_price", rule!(require(seller_badge.resource_address())), LOCKED)
+                .default(rule!(allow_all), LOCKED);
+
+            let mut nft_sale: NftSaleComponent = Self {
+                items_vault: Vault::with_bucket(items),
+                payment_vault: Vault::new(payment_resource),
+                default_price: price_per_item,
+                item_prices: HashMap::new()
+            }
+            .instantiate();
+            nft_sale.add_access_check(access_rules);
+
+            (nft_sale.globalize(), seller_badge)
+        }
+
+        // Buys the item with the given id, returning it along with the change.
+        pub fn buy_item(&mut self, item_id: NonFungibleLocalId, payment: Bucket) -> (Bucket, Bucket) {
+            assert!(
+                self.items_vault.non_fungible_local_ids().contains(&item_id),
+                "The item {:?} is not for sale",
+                item_id
+            );
+            self.sell_item(item_id, payment)
+        }
+
+        // Buys a random item out of the items which are still for sale, returning it along with
+        // the change. The item is picked through a UUID generated by the transaction, which is
+        // unpredictable enough for collectibles but should not be relied upon where the item
+        // picked carries a lot of value. As the item is sold at its own price, the purchase fails
+        // when the item picked costs more than `max_price`, rather than charging the buyer more
+        // than they meant to pay.
+        pub fn buy_random_item(&mut self, payment: Bucket, max_price: Decimal) -> (Bucket, Bucket) {
+            let available_items: BTreeSet<NonFungibleLocalId> = self.items_vault.non_fungible_local_ids();
+            assert!(!available_items.is_empty(), "All of the items have been sold");
+
+            let index: usize = (Runtime::generate_uuid() % available_items.len() as u128) as usize;
+            let item_id: NonFungibleLocalId = available_items.into_iter().nth(index).unwrap();
+            let price: Decimal = self.item_price(item_id.clone());
+            assert!(
+                price <= max_price,
+                "The item picked costs {}, which is more than the maximum price of {}",
+                price,
+                max_price
+            );
+            self.sell_item(item_id, payment)
+        }
+
+        pub fn available_items(&self) -> BTreeSet<NonFungibleLocalId> {
+            self.items_vault.non_fungible_local_ids()
+        }
+
+        pub fn item_price(&self, item_id: NonFungibleLocalId) -> Decimal {
+            *self.item_prices.get(&item_id).unwrap_or(&self.default_price)
+        }
+
+        pub fn withdraw_funds(&mut self, amount: Decimal) -> Bucket {
+            self.payment_vault.take(amount)
+        }
+
+        pub fn withdraw_unsold_items(&mut self) -> Bucket {
+            self.items_vault.take_all()
+        }
+
+        // Changes the price of the items which do not have a price of their own.
+        pub fn change_price(&mut self, price: Decimal) {
+            assert!(price > Decimal::zero(), "The price per item must be positive");
+            self.default_price = price;
+        }
+
+        // Gives the item with the given id a price of its own, different from the default price.
+        pub fn set_item_price(&mut self, item_id: NonFungibleLocalId, price: Decimal) {
+            assert!(price > Decimal::zero(), "The price of the item must be positive");
+            self.item_prices.insert(item_id, price);
+        }
+
+        // Sells the item with the given id for its price, returning it along with the change.
+        fn sell_item(&mut self, item_id: NonFungibleLocalId, mut payment: Bucket) -> (Bucket, Bucket) {
+            assert!(
+                payment.resource_address() == self.payment_vault.resource_address(),
+                "The items can only be bought with the payment resource of the sale"
+            );
+            let price: Decimal = self.item_price(item_id.clone());
+            assert!(
+                payment.amount() >= price,
+                "Insufficient payment: the item costs {} but only {} was provided",
+                price,
+                payment.amount()
+            );
+
+            self.payment_vault.put(payment.take(price));
+            (self.items_vault.take_non_fungible(&item_id), payment)
+        }
+    }
+}
+
+// The data of the items of a collection created by the sale.
+#[derive(NonFungibleData)]
+pub struct CollectibleItem {
+    name: String,
+    image_url: String
+}
@@ -0,0 +1,575 @@
+use scrypto::prelude::*;
+
+// The pricing of the tokens sold by the token sale, in other words, the way in which the price of a
+// single token is determined for each of the accepted payment resources.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone)]
+pub enum Pricing {
+    // A fixed price which only changes when the seller changes it.
+    Fixed(Decimal),
+
+    // A Dutch auction where the price starts at `start_price` and decays down to `floor_price`
+    // between the `start_epoch` and the `end_epoch`, staying at the floor price afterwards.
+    DutchAuction {
+        start_price: Decimal,
+        floor_price: Decimal,
+        start_epoch: u64,
+        end_epoch: u64,
+        decay: PriceDecay
+    },
+
+    // A bonding curve where the price follows the `curve` based on the amount of tokens sold. When
+    // `sell_back_enabled` is set, holders can sell their tokens back along the curve.
+    BondingCurve {
+        curve: BondingCurve,
+        sell_back_enabled: bool
+    },
+
+    // A price of `usd_price` in USD, converted into the payment resource through the price
+    // `oracle`. The oracle is considered outdated when it has not been updated for more than
+    // `max_staleness_epochs`, in which case the `fallback` decides how the tokens are priced. The
+    // oracle is expected to have a `get_price` method returning the price of a single unit of the
+    // payment resource in USD along with the epoch at which the price was last updated, like the
+    // `MockOracle` blueprint of this package.
+    UsdPrice {
+        usd_price: Decimal,
+        oracle: ComponentAddress,
+        max_staleness_epochs: u64,
+        fallback: OracleFallback
+    },
+
+    // Prices which change in tiers, where `tiers` lists the threshold from which each tier starts
+    // along with the price of the tokens in that tier, in increasing order of threshold starting at
+    // zero. The `basis` decides what the thresholds apply to, and a purchase spanning several tiers
+    // pays the price of each tier for the tokens which fall in it.
+    Tiered {
+        tiers: Vec<(Decimal, Decimal)>,
+        basis: TierBasis
+    }
+}
+
+// What the thresholds of tiered prices apply to.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone)]
+pub enum TierBasis {
+    // The thresholds apply to the amount of tokens bought in a single purchase, giving volume
+    // discounts to larger purchases.
+    PurchaseVolume,
+
+    // The thresholds apply to the amount of tokens sold so far, the price advancing to the next
+    // stage as the sales cross each threshold.
+    CumulativeSales
+}
+
+// How the price of a Dutch auction decays over time.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone)]
+pub enum PriceDecay {
+    // The price decreases by the same amount every epoch, reaching the floor price at the end
+    // epoch.
+    Linear,
+
+    // The price decreases by `rate_per_epoch` of its value every epoch, where
+    // 0 < rate_per_epoch < 1. The price never goes below the floor price and is at the floor price
+    // from the end epoch onwards.
+    Exponential { rate_per_epoch: Decimal }
+}
+
+// How the tokens are priced when the price oracle of a USD price is outdated.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone)]
+pub enum OracleFallback {
+    // No tokens can be bought until the oracle is updated.
+    Halt,
+
+    // The tokens are bought at the given fixed price in the payment resource.
+    FixedPrice(Decimal),
+
+    // The tokens are bought at the last price reported by the oracle, however outdated it is.
+    LastOraclePrice
+}
+
+// The price of a single token as a function of the amount of tokens sold `s`.
+#[derive(ScryptoCategorize, ScryptoEncode, ScryptoDecode, LegacyDescribe, Debug, Clone)]
+pub enum BondingCurve {
+    // A price of `initial_price + slope * s`.
+    Linear { initial_price: Decimal, slope: Decimal },
+
+    // A price of `initial_price * e^(growth_rate * s)`.
+    Exponential { initial_price: Decimal, growth_rate: Decimal },
+
+    // A price of `coefficient * s^exponent`.
+    Power { coefficient: Decimal, exponent: u32 }
+}
+
+impl Pricing {
+    // Asserts that the pricing makes sense, panicking if it does not.
+    pub fn validate(&self) {
+        match self {
+            Self::Fixed(price) => {
+                assert!(*price > Decimal::zero(), "The price per token must be positive");
+            }
+            Self::DutchAuction { start_price, floor_price, start_epoch, end_epoch, decay } => {
+                assert!(*floor_price > Decimal::zero(), "The floor price must be positive");
+                assert!(start_price >= floor_price, "The start price must not be below the floor price");
+                assert!(start_epoch < end_epoch, "The auction must end after it starts");
+                if let PriceDecay::Exponential { rate_per_epoch } = decay {
+                    assert!(
+                        *rate_per_epoch > Decimal::zero() && *rate_per_epoch < Decimal::one(),
+                        "The decay rate must be between 0 and 1"
+                    );
+                }
+            }
+            Self::BondingCurve { curve, .. } => curve.validate(),
+            Self::UsdPrice { usd_price, fallback, .. } => {
+                assert!(*usd_price > Decimal::zero(), "The USD price per token must be positive");
+                if let OracleFallback::FixedPrice(price) = fallback {
+                    assert!(*price > Decimal::zero(), "The fallback price must be positive");
+                }
+            }
+            Self::Tiered { tiers, .. } => {
+                assert!(
+                    tiers.first().map(|(threshold, _)| threshold.is_zero()) == Some(true),
+                    "The first tier must start at zero"
+                );
+                assert!(
+                    tiers.windows(2).all(|pair| pair[0].0 < pair[1].0),
+                    "The thresholds of the tiers must be increasing"
+                );
+                assert!(
+                    tiers.iter().all(|(_, price)| *price > Decimal::zero()),
+                    "The price of every tier must be positive"
+                );
+            }
+        }
+    }
+
+    // Gets the cost of buying `amount` tokens at the given epoch when `tokens_sold` tokens have
+    // already been sold.
+    pub fn cost_of_tokens(&self, epoch: u64, tokens_sold: Decimal, amount: Decimal) -> Decimal {
+        match self {
+            Self::BondingCurve { curve, .. } => curve.cost_of_tokens(tokens_sold, amount),
+            Self::Tiered { tiers, basis } => {
+                let start: Decimal = basis.start(tokens_sold);
+                tiers_cost(tiers, start + amount) - tiers_cost(tiers, start)
+            }
+            _ => amount * self.price_per_token(epoch, tokens_sold)
+        }
+    }
+
+    // Gets the amount of tokens which the given payment buys at the given epoch when `tokens_sold`
+    // tokens have already been sold.
+    pub fn tokens_for_payment(&self, epoch: u64, tokens_sold: Decimal, payment: Decimal) -> Decimal {
+        match self {
+            Self::BondingCurve { curve, .. } => curve.tokens_for_payment(tokens_sold, payment),
+            Self::Tiered { tiers, basis } => tiers_tokens_for_payment(tiers, basis.start(tokens_sold), payment),
+            _ => payment / self.price_per_token(epoch, tokens_sold)
+        }
+    }
+
+    // Gets the price of a single token at the given epoch when `tokens_sold` tokens have already
+    // been sold. For volume discounts, this is the price of the first token of a purchase.
+    pub fn price_per_token(&self, epoch: u64, tokens_sold: Decimal) -> Decimal {
+        match self {
+            Self::Fixed(price) => *price,
+            Self::DutchAuction { start_price, floor_price, start_epoch, end_epoch, decay } => {
+                if epoch <= *start_epoch {
+                    *start_price
+                } else if epoch >= *end_epoch {
+                    *floor_price
+                } else {
+                    let elapsed_epochs: u64 = epoch - start_epoch;
+                    match decay {
+                        PriceDecay::Linear => {
+                            *start_price
+                                - (*start_price - *floor_price) * Decimal::from(elapsed_epochs)
+                                    / Decimal::from(end_epoch - start_epoch)
+                        }
+                        PriceDecay::Exponential { rate_per_epoch } => {
+                            let price: Decimal = *start_price * (Decimal::one() - *rate_per_epoch).powi(elapsed_epochs as i64);
+                            if price > *floor_price {
+                                price
+                            } else {
+                                *floor_price
+                            }
+                        }
+                    }
+                }
+            }
+            Self::BondingCurve { curve, .. } => curve.price_at(tokens_sold),
+            Self::UsdPrice { usd_price, oracle, max_staleness_epochs, fallback } => {
+                let (oracle_price, last_updated): (Decimal, u64) = borrow_component!(*oracle).call("get_price", args!());
+                let is_outdated: bool = epoch.saturating_sub(last_updated) > *max_staleness_epochs;
+                match (is_outdated, fallback) {
+                    (true, OracleFallback::Halt) => {
+                        panic!("The price oracle is outdated, it was last updated at epoch {}", last_updated)
+                    }
+                    (true, OracleFallback::FixedPrice(price)) => *price,
+                    _ => {
+                        assert!(oracle_price > Decimal::zero(), "The price oracle reported an invalid price");
+                        *usd_price / oracle_price
+                    }
+                }
+            }
+            Self::Tiered { tiers, basis } => tier_at(tiers, basis.start(tokens_sold)).0
+        }
+    }
+}
+
+impl TierBasis {
+    // Gets the position along the tiers at which a purchase starts when `tokens_sold` tokens have
+    // already been sold.
+    fn start(&self, tokens_sold: Decimal) -> Decimal {
+        match self {
+            Self::PurchaseVolume => Decimal::zero(),
+            Self::CumulativeSales => tokens_sold
+        }
+    }
+}
+
+impl BondingCurve {
+    // Asserts that the bonding curve makes sense, panicking if it does not.
+    pub fn validate(&self) {
+        match self {
+            Self::Linear { initial_price, slope } => {
+                assert!(*initial_price > Decimal::zero(), "The initial price must be positive");
+                assert!(*slope >= Decimal::zero(), "The slope must not be negative");
+            }
+            Self::Exponential { initial_price, growth_rate } => {
+                assert!(*initial_price > Decimal::zero(), "The initial price must be positive");
+                assert!(*growth_rate > Decimal::zero(), "The growth rate must be positive");
+            }
+            Self::Power { coefficient, exponent } => {
+                assert!(*coefficient > Decimal::zero(), "The coefficient must be positive");
+                assert!(*exponent > 0, "The exponent must be positive");
+            }
+        }
+    }
+
+    // Gets the price of a single token once `tokens_sold` tokens have been sold.
+    pub fn price_at(&self, tokens_sold: Decimal) -> Decimal {
+        match self {
+            Self::Linear { initial_price, slope } => *initial_price + *slope * tokens_sold,
+            Self::Exponential { initial_price, growth_rate } => *initial_price * exp(*growth_rate * tokens_sold),
+            Self::Power { coefficient, exponent } => *coefficient * tokens_sold.powi(*exponent as i64)
+        }
+    }
+
+    // Gets the area under the curve between zero and `tokens_sold`, which is the total cost of the
+    // first `tokens_sold` tokens.
+    pub fn integral(&self, tokens_sold: Decimal) -> Decimal {
+        match self {
+            Self::Linear { initial_price, slope } => {
+                *initial_price * tokens_sold + *slope * tokens_sold * tokens_sold / dec!("2")
+            }
+            Self::Exponential { initial_price, growth_rate } => {
+                *initial_price * (exp(*growth_rate * tokens_sold) - Decimal::one()) / *growth_rate
+            }
+            Self::Power { coefficient, exponent } => {
+                *coefficient * tokens_sold.powi(*exponent as i64 + 1) / Decimal::from(*exponent as u64 + 1)
+            }
+        }
+    }
+
+    // Gets the cost of buying `amount` tokens when `tokens_sold` tokens have already been sold.
+    pub fn cost_of_tokens(&self, tokens_sold: Decimal, amount: Decimal) -> Decimal {
+        self.integral(tokens_sold + amount) - self.integral(tokens_sold)
+    }
+
+    // Gets the amount of tokens which the given payment buys when `tokens_sold` tokens have already
+    // been sold. As the cost of the tokens grows with the amount bought, the amount is found
+    // through a bisection between an amount which the payment can afford and one which it can not.
+    pub fn tokens_for_payment(&self, tokens_sold: Decimal, payment: Decimal) -> Decimal {
+        let mut low: Decimal = Decimal::zero();
+        let mut high: Decimal = Decimal::one();
+        while self.cost_of_tokens(tokens_sold, high) <= payment {
+            low = high;
+            high = high * dec!("2");
+        }
+
+        for _ in 0..BISECTION_ITERATIONS {
+            let middle: Decimal = (low + high) / dec!("2");
+            if middle == low || middle == high {
+                break;
+            }
+            if self.cost_of_tokens(tokens_sold, middle) <= payment {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        low
+    }
+}
+
+// Gets the price of the tier which the given position along the tiers falls in, along with the
+// threshold at which the next tier starts if there is one.
+fn tier_at(tiers: &[(Decimal, Decimal)], position: Decimal) -> (Decimal, Option<Decimal>) {
+    let index: usize = tiers.iter().rposition(|(threshold, _)| *threshold <= position).unwrap_or(0);
+    (tiers[index].1, tiers.get(index + 1).map(|(threshold, _)| *threshold))
+}
+
+// Gets the total cost of the tokens from zero up to the given position along the tiers.
+fn tiers_cost(tiers: &[(Decimal, Decimal)], position: Decimal) -> Decimal {
+    let mut cost: Decimal = Decimal::zero();
+    for (index, (threshold, price)) in tiers.iter().enumerate() {
+        if position <= *threshold {
+            break;
+        }
+        let tier_end: Decimal = match tiers.get(index + 1) {
+            Some((next_threshold, _)) if *next_threshold < position => *next_threshold,
+            _ => position
+        };
+        cost += (tier_end - *threshold) * *price;
+    }
+    cost
+}
+
+// Gets the amount of tokens which the given payment buys starting from the given position along the
+// tiers, going through the tiers until the payment runs out.
+fn tiers_tokens_for_payment(tiers: &[(Decimal, Decimal)], start: Decimal, payment: Decimal) -> Decimal {
+    let mut position: Decimal = start;
+    let mut remaining_payment: Decimal = payment;
+    loop {
+        let (price, next_threshold): (Decimal, Option<Decimal>) = tier_at(tiers, position);
+        match next_threshold {
+            Some(next_threshold) if (next_threshold - position) * price < remaining_payment => {
+                remaining_payment -= (next_threshold - position) * price;
+                position = next_threshold;
+            }
+            _ => return position + remaining_payment / price - start
+        }
+    }
+}
+
+// The maximum number of iterations of the bisection, enough to narrow the amount of tokens down to
+// the precision of a `Decimal`.
+const BISECTION_ITERATIONS: usize = 192;
+
+// The largest exponent for which `exp` is computed. `e^100` is around 2.7 * 10^43, which leaves
+// room below the largest `Decimal` for the prices and costs computed from it.
+const MAX_EXP_ARGUMENT: u32 = 100;
+
+// Computes `e^x` for a non-negative `x`. The exponent is halved until the Taylor series of `e^x`
+// converges quickly, and the result of the series is then squared back as many times as the
+// exponent was halved.
+fn exp(x: Decimal) -> Decimal {
+    assert!(
+        x <= Decimal::from(MAX_EXP_ARGUMENT),
+        "The bonding curve overflows, its exponent {} exceeds {}",
+        x,
+        MAX_EXP_ARGUMENT
+    );
+    let mut reduced: Decimal = x;
+    let mut halvings: u32 = 0;
+    while reduced > dec!("0.5") {
+        reduced = reduced / dec!("2");
+        halvings += 1;
+    }
+
+    let mut term: Decimal = Decimal::one();
+    let mut sum: Decimal = Decimal::one();
+    for n in 1..=24u64 {
+        term = term * reduced / Decimal::from(n);
+        sum += term;
+    }
+
+    for _ in 0..halvings {
+        sum = sum * sum;
+    }
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Asserts that two amounts are equal up to the given tolerance.
+    fn assert_close(actual: Decimal, expected: Decimal, tolerance: Decimal) {
+        let difference: Decimal = if actual > expected { actual - expected } else { expected - actual };
+        assert!(
+            difference <= tolerance,
+            "Expected {} but got {}, which is off by more than {}",
+            expected,
+            actual,
+            tolerance
+        );
+    }
+
+    #[test]
+    fn exp_matches_known_values() {
+        assert_eq!(exp(Decimal::zero()), Decimal::one());
+        assert_close(exp(dec!("0.5")), dec!("1.648721270700128146"), dec!("0.000000000000001"));
+        assert_close(exp(Decimal::one()), dec!("2.718281828459045235"), dec!("0.000000000000001"));
+        assert_close(exp(dec!("10")), dec!("22026.465794806716516957"), dec!("0.00000001"));
+    }
+
+    #[test]
+    fn exp_is_computed_up_to_its_bound() {
+        let result: Decimal = exp(Decimal::from(MAX_EXP_ARGUMENT));
+        assert_close(
+            result / dec!("10000000000000000000000000000000000000000000"),
+            dec!("2.688117141816135448"),
+            dec!("0.000000001")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The bonding curve overflows")]
+    fn exp_panics_beyond_its_bound() {
+        exp(Decimal::from(MAX_EXP_ARGUMENT) + Decimal::one());
+    }
+
+    #[test]
+    fn linear_curve_costs_the_area_under_the_curve() {
+        let curve: BondingCurve = BondingCurve::Linear {
+            initial_price: dec!("1"),
+            slope: dec!("0.1")
+        };
+
+        // Between 10 and 20 tokens sold, the price goes from 2 up to 3
+        assert_eq!(curve.cost_of_tokens(dec!("10"), dec!("10")), dec!("25"));
+    }
+
+    #[test]
+    fn tokens_for_payment_is_the_inverse_of_the_cost() {
+        let curves: Vec<BondingCurve> = vec![
+            BondingCurve::Linear {
+                initial_price: dec!("1"),
+                slope: dec!("0.1")
+            },
+            BondingCurve::Exponential {
+                initial_price: dec!("1"),
+                growth_rate: dec!("0.01")
+            },
+            BondingCurve::Power {
+                coefficient: dec!("0.5"),
+                exponent: 2
+            }
+        ];
+        for curve in curves {
+            for (tokens_sold, amount) in [(dec!("0"), dec!("5")), (dec!("10"), dec!("7.5")), (dec!("250"), dec!("0.25"))] {
+                let payment: Decimal = curve.cost_of_tokens(tokens_sold, amount);
+                assert_close(curve.tokens_for_payment(tokens_sold, payment), amount, dec!("0.000000001"));
+            }
+        }
+    }
+
+    // A Dutch auction from a price of 10 down to a floor price of 2 between epochs 100 and 200.
+    fn dutch_auction(decay: PriceDecay) -> Pricing {
+        Pricing::DutchAuction {
+            start_price: dec!("10"),
+            floor_price: dec!("2"),
+            start_epoch: 100,
+            end_epoch: 200,
+            decay: decay
+        }
+    }
+
+    #[test]
+    fn linear_decay_reaches_the_floor_at_the_end() {
+        let pricing: Pricing = dutch_auction(PriceDecay::Linear);
+        assert_eq!(pricing.price_per_token(50, Decimal::zero()), dec!("10"));
+        assert_eq!(pricing.price_per_token(100, Decimal::zero()), dec!("10"));
+        assert_eq!(pricing.price_per_token(125, Decimal::zero()), dec!("8"));
+        assert_eq!(pricing.price_per_token(150, Decimal::zero()), dec!("6"));
+        assert_eq!(pricing.price_per_token(200, Decimal::zero()), dec!("2"));
+        assert_eq!(pricing.price_per_token(300, Decimal::zero()), dec!("2"));
+    }
+
+    #[test]
+    fn exponential_decay_is_clamped_to_the_floor() {
+        let pricing: Pricing = dutch_auction(PriceDecay::Exponential {
+            rate_per_epoch: dec!("0.1")
+        });
+        assert_eq!(pricing.price_per_token(100, Decimal::zero()), dec!("10"));
+        assert_close(pricing.price_per_token(101, Decimal::zero()), dec!("9"), dec!("0.000000001"));
+        assert_close(pricing.price_per_token(110, Decimal::zero()), dec!("3.486784401"), dec!("0.000000001"));
+
+        // 10 * 0.9^20 is about 1.22, which is below the floor price
+        assert_eq!(pricing.price_per_token(120, Decimal::zero()), dec!("2"));
+        assert_eq!(pricing.price_per_token(150, Decimal::zero()), dec!("2"));
+        assert_eq!(pricing.price_per_token(200, Decimal::zero()), dec!("2"));
+    }
+
+    #[test]
+    fn exponential_decay_is_at_the_floor_from_the_end_epoch() {
+        // A slow decay which would still be above the floor price at the end epoch
+        let pricing: Pricing = dutch_auction(PriceDecay::Exponential {
+            rate_per_epoch: dec!("0.001")
+        });
+        assert!(pricing.price_per_token(199, Decimal::zero()) > dec!("8"));
+        assert_eq!(pricing.price_per_token(200, Decimal::zero()), dec!("2"));
+    }
+
+    // Tiers of 100 tokens at a price of 1, then 100 tokens at a price of 2, then a price of 3.
+    fn tiers() -> Vec<(Decimal, Decimal)> {
+        vec![
+            (dec!("0"), dec!("1")),
+            (dec!("100"), dec!("2")),
+            (dec!("200"), dec!("3"))
+        ]
+    }
+
+    #[test]
+    fn tier_at_finds_the_tier_of_a_position() {
+        assert_eq!(tier_at(&tiers(), dec!("0")), (dec!("1"), Some(dec!("100"))));
+        assert_eq!(tier_at(&tiers(), dec!("99.9")), (dec!("1"), Some(dec!("100"))));
+        assert_eq!(tier_at(&tiers(), dec!("100")), (dec!("2"), Some(dec!("200"))));
+        assert_eq!(tier_at(&tiers(), dec!("1000")), (dec!("3"), None));
+    }
+
+    #[test]
+    fn tiers_cost_adds_up_the_cost_of_each_tier() {
+        assert_eq!(tiers_cost(&tiers(), dec!("0")), dec!("0"));
+        assert_eq!(tiers_cost(&tiers(), dec!("50")), dec!("50"));
+        assert_eq!(tiers_cost(&tiers(), dec!("100")), dec!("100"));
+        assert_eq!(tiers_cost(&tiers(), dec!("150")), dec!("200"));
+        assert_eq!(tiers_cost(&tiers(), dec!("250")), dec!("450"));
+    }
+
+    #[test]
+    fn tiers_tokens_for_payment_crosses_tiers() {
+        // Within a single tier
+        assert_eq!(tiers_tokens_for_payment(&tiers(), dec!("0"), dec!("40")), dec!("40"));
+
+        // From the first tier into the second: 50 tokens for 50, then 50 tokens for 100
+        assert_eq!(tiers_tokens_for_payment(&tiers(), dec!("50"), dec!("150")), dec!("100"));
+
+        // Through all of the tiers: 100 tokens for 100, 100 tokens for 200, then 100 tokens for 300
+        assert_eq!(tiers_tokens_for_payment(&tiers(), dec!("0"), dec!("600")), dec!("300"));
+
+        // Exactly up to a threshold
+        assert_eq!(tiers_tokens_for_payment(&tiers(), dec!("0"), dec!("100")), dec!("100"));
+    }
+
+    #[test]
+    fn tiered_pricing_buys_what_it_costs() {
+        for basis in [TierBasis::PurchaseVolume, TierBasis::CumulativeSales] {
+            let pricing: Pricing = Pricing::Tiered {
+                tiers: tiers(),
+                basis: basis
+            };
+            for (tokens_sold, amount) in [(dec!("0"), dec!("30")), (dec!("80"), dec!("150")), (dec!("220"), dec!("10"))] {
+                let payment: Decimal = pricing.cost_of_tokens(1, tokens_sold, amount);
+                assert_eq!(pricing.tokens_for_payment(1, tokens_sold, payment), amount);
+            }
+        }
+    }
+
+    #[test]
+    fn volume_discounts_ignore_the_tokens_sold() {
+        let pricing: Pricing = Pricing::Tiered {
+            tiers: tiers(),
+            basis: TierBasis::PurchaseVolume
+        };
+        assert_eq!(pricing.cost_of_tokens(1, dec!("500"), dec!("150")), dec!("200"));
+        assert_eq!(pricing.price_per_token(1, dec!("500")), dec!("1"));
+    }
+
+    #[test]
+    fn tokens_for_payment_never_costs_more_than_the_payment() {
+        let curve: BondingCurve = BondingCurve::Exponential {
+            initial_price: dec!("2"),
+            growth_rate: dec!("0.05")
+        };
+        let tokens: Decimal = curve.tokens_for_payment(dec!("20"), dec!("100"));
+        assert!(curve.cost_of_tokens(dec!("20"), tokens) <= dec!("100"));
+    }
+}
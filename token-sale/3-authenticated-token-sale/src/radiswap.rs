@@ -0,0 +1,155 @@
+use scrypto::prelude::*;
+
+// The Radiswap constant product pool of the simple-dex example, ported to this version of Scrypto
+// so that the token sale can seed the liquidity of a pool of its tokens once it is finalized. The
+// pool holds the reserves of two tokens, swaps one for the other at the price set by the ratio of
+// the reserves, and tracks the liquidity provided to it through pool share tokens.
+#[blueprint]
+mod radiswap {
+    struct Radiswap {
+        // The vaults where the reserves of the two tokens of the pool are stored.
+        vault_a: Vault,
+        vault_b: Vault,
+
+        // The resource address of the pool share tokens, along with the vault where the badge with
+        // the authority to mint and burn them is stored.
+        pool_share_resource: ResourceAddress,
+        pool_share_minter_badge: Vault,
+
+        // The fee charged on the input tokens of each swap, where 0 <= fee <= 1.
+        fee: Decimal
+    }
+
+    impl Radiswap {
+        // Creates a new liquidity pool of the two tokens sent to it, returning the pool share
+        // tokens representing the whole of the pool.
+        pub fn instantiate_pool(
+            bucket_a: Bucket,
+            bucket_b: Bucket,
+            pool_shares_initial_supply: Decimal,
+            fee: Decimal
+        ) -> (ComponentAddress, Bucket) {
+            assert!(
+                !bucket_a.is_empty() && !bucket_b.is_empty(),
+                "You must pass in an initial supply of each token"
+            );
+            assert!(
+                bucket_a.resource_address() != bucket_b.resource_address(),
+                "The two tokens of the pool must be different"
+            );
+            assert!(pool_shares_initial_supply > Decimal::zero(), "The initial supply of pool shares must be positive");
+            assert!(fee >= Decimal::zero() && fee <= Decimal::one(), "The fee must be between 0 and 1");
+
+            // Creating a badge which can mint and burn the pool share tokens
+            let pool_share_minter_badge: Bucket = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_NONE)
+                .metadata("name", "Pool Share Minter Badge")
+                .mint_initial_supply(1);
+            let pool_shares: Bucket = ResourceBuilder::new_fungible()
+                .divisibility(DIVISIBILITY_MAXIMUM)
+                .metadata("name", "Pool Share")
+                .metadata("symbol", "SHARE")
+                .mintable(rule!(require(pool_share_minter_badge.resource_address())), LOCKED)
+                .burnable(rule!(require(pool_share_minter_badge.resource_address())), LOCKED)
+                .mint_initial_supply(pool_shares_initial_supply);
+
+            let radiswap: ComponentAddress = Self {
+                vault_a: Vault::with_bucket(bucket_a),
+                vault_b: Vault::with_bucket(bucket_b),
+                pool_share_resource: pool_shares.resource_address(),
+                pool_share_minter_badge: Vault::with_bucket(pool_share_minter_badge),
+                fee: fee
+            }
+            .instantiate()
+            .globalize();
+
+            (radiswap, pool_shares)
+        }
+
+        // Swaps the given input tokens for the other token of the pool.
+        pub fn swap(&mut self, input_tokens: Bucket) -> Bucket {
+            let (input_tokens_vault, output_tokens_vault): (&mut Vault, &mut Vault) =
+                if input_tokens.resource_address() == self.vault_a.resource_address() {
+                    (&mut self.vault_a, &mut self.vault_b)
+                } else if input_tokens.resource_address() == self.vault_b.resource_address() {
+                    (&mut self.vault_b, &mut self.vault_a)
+                } else {
+                    panic!("The given input tokens do not belong to this liquidity pool")
+                };
+
+            // The output keeps the product of the reserves constant, with the fee taken out of the
+            // input tokens
+            let input_amount: Decimal = input_tokens.amount() * (Decimal::one() - self.fee);
+            let output_amount: Decimal =
+                input_amount * output_tokens_vault.amount() / (input_tokens_vault.amount() + input_amount);
+
+            input_tokens_vault.put(input_tokens);
+            output_tokens_vault.take(output_amount)
+        }
+
+        // Adds liquidity to the pool in the ratio of its reserves, returning the tokens which
+        // exceed that ratio along with the pool share tokens minted for the liquidity added.
+        pub fn add_liquidity(&mut self, bucket_a: Bucket, bucket_b: Bucket) -> (Bucket, Bucket, Bucket) {
+            // Sorting the buckets in the order of the vaults
+            let (mut bucket_a, mut bucket_b): (Bucket, Bucket) = if bucket_a.resource_address()
+                == self.vault_a.resource_address()
+                && bucket_b.resource_address() == self.vault_b.resource_address()
+            {
+                (bucket_a, bucket_b)
+            } else if bucket_a.resource_address() == self.vault_b.resource_address()
+                && bucket_b.resource_address() == self.vault_a.resource_address()
+            {
+                (bucket_b, bucket_a)
+            } else {
+                panic!("One of the tokens does not belong to the pool")
+            };
+
+            // Taking as much of each token as keeps the ratio of the reserves
+            let reserve_a: Decimal = self.vault_a.amount();
+            let reserve_b: Decimal = self.vault_b.amount();
+            let (amount_a, amount_b): (Decimal, Decimal) = if reserve_a.is_zero() || reserve_b.is_zero() {
+                (bucket_a.amount(), bucket_b.amount())
+            } else if bucket_a.amount() * reserve_b > bucket_b.amount() * reserve_a {
+                (bucket_b.amount() * reserve_a / reserve_b, bucket_b.amount())
+            } else {
+                (bucket_a.amount(), bucket_a.amount() * reserve_b / reserve_a)
+            };
+            self.vault_a.put(bucket_a.take(amount_a));
+            self.vault_b.put(bucket_b.take(amount_b));
+
+            // Minting pool shares in proportion to the liquidity added
+            let mut pool_share_manager: ResourceManager = borrow_resource_manager!(self.pool_share_resource);
+            let pool_shares_amount: Decimal = if pool_share_manager.total_supply().is_zero() || reserve_a.is_zero() {
+                dec!("100")
+            } else {
+                amount_a * pool_share_manager.total_supply() / reserve_a
+            };
+            let pool_shares: Bucket = self
+                .pool_share_minter_badge
+                .authorize(|| pool_share_manager.mint(pool_shares_amount));
+
+            (bucket_a, bucket_b, pool_shares)
+        }
+
+        // Removes the share of the reserves corresponding to the given pool shares, burning them.
+        pub fn remove_liquidity(&mut self, pool_shares: Bucket) -> (Bucket, Bucket) {
+            assert!(
+                pool_shares.resource_address() == self.pool_share_resource,
+                "Wrong token type passed in"
+            );
+
+            let share: Decimal =
+                pool_shares.amount() / borrow_resource_manager!(self.pool_share_resource).total_supply();
+            self.pool_share_minter_badge.authorize(|| pool_shares.burn());
+
+            (
+                self.vault_a.take(self.vault_a.amount() * share),
+                self.vault_b.take(self.vault_b.amount() * share)
+            )
+        }
+
+        pub fn reserves(&self) -> (Decimal, Decimal) {
+            (self.vault_a.amount(), self.vault_b.amount())
+        }
+    }
+}
@@ -0,0 +1,1436 @@
+use boilerplate::{BondingCurve, OracleFallback, Role};
+use radix_engine::transaction::TransactionReceipt;
+use scrypto::prelude::*;
+use scrypto_unit::*;
+use transaction::builder::ManifestBuilder;
+use transaction::model::TransactionManifest;
+
+// A token sale instantiated on a local ledger simulator along with the account of its seller, who
+// holds the seller badge, and the account of a user without any badge.
+struct TestSale {
+    test_runner: TestRunner,
+    seller_public_key: EcdsaSecp256k1PublicKey,
+    seller_account: ComponentAddress,
+    user_public_key: EcdsaSecp256k1PublicKey,
+    user_account: ComponentAddress,
+    package_address: PackageAddress,
+    component: ComponentAddress,
+    useful_token: ResourceAddress,
+    seller_badge: ResourceAddress,
+    buyer_receipt: ResourceAddress,
+    vesting_nft: ResourceAddress,
+    price_manager_badge: ResourceAddress,
+    treasurer_badge: ResourceAddress,
+}
+
+impl TestSale {
+    // Instantiates a sale of 1000 UsefulTokens at 0.5 XRD per token.
+    fn new() -> Self {
+        Self::with_epochs(0, 1000)
+    }
+
+    // Instantiates a sale of 1000 UsefulTokens at 0.5 XRD per token, running from the start epoch
+    // to the end epoch.
+    fn with_epochs(start_epoch: u64, end_epoch: u64) -> Self {
+        let mut test_runner = TestRunner::new(true);
+        let (seller_public_key, _private_key, seller_account) = test_runner.new_allocated_account();
+        let (user_public_key, _private_key, user_account) = test_runner.new_allocated_account();
+        let package_address = test_runner.compile_and_publish(this_package!());
+
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                package_address,
+                "TokenSale",
+                "instantiate_token_sale",
+                args!(
+                    "UsefulToken".to_string(),
+                    "USEFUL".to_string(),
+                    "A very useful token".to_string(),
+                    "https://example.com/useful.png".to_string(),
+                    18u8,
+                    dec!("1000"),
+                    RADIX_TOKEN,
+                    dec!("0.5"),
+                    start_epoch,
+                    end_epoch
+                ),
+            )
+            .call_method(
+                seller_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&seller_public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt.expect_commit_success();
+
+        // The resources are created in order: the token, the seller badge, the internal admin
+        // badge, the allowlist badges, the buyer receipts, the referrer badges, the vesting NFTs
+        // and then the badges of the roles.
+        let new_resources = &receipt.expect_commit().entity_changes.new_resource_addresses;
+        Self {
+            component: receipt.expect_commit().entity_changes.new_component_addresses[0],
+            useful_token: new_resources[0],
+            seller_badge: new_resources[1],
+            buyer_receipt: new_resources[4],
+            vesting_nft: new_resources[6],
+            price_manager_badge: new_resources[7],
+            treasurer_badge: new_resources[8],
+            test_runner,
+            seller_public_key,
+            seller_account,
+            user_public_key,
+            user_account,
+            package_address,
+        }
+    }
+
+    fn execute_as_seller(&mut self, manifest: TransactionManifest) -> TransactionReceipt {
+        let receipt = self.test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&self.seller_public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt
+    }
+
+    fn execute_as_user(&mut self, manifest: TransactionManifest) -> TransactionReceipt {
+        let receipt = self.test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&self.user_public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt
+    }
+
+    // Buys UsefulTokens with the given amount of XRD from the user account.
+    fn buy(&mut self, amount: Decimal) -> TransactionReceipt {
+        self.buy_with(RADIX_TOKEN, amount)
+    }
+
+    // Buys UsefulTokens with the given amount of the given payment resource from the user account.
+    fn buy_with(&mut self, payment_resource: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account_by_amount(self.user_account, amount, payment_resource)
+            .take_from_worktop(payment_resource, |builder, bucket_id| {
+                builder.call_method(self.component, "buy", args!(bucket_id))
+            })
+            .call_method(
+                self.user_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_user(manifest)
+    }
+
+    // Sells the given amount of UsefulTokens from the user account back for XRD.
+    fn sell(&mut self, amount: Decimal) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account_by_amount(self.user_account, amount, self.useful_token)
+            .take_from_worktop(self.useful_token, |builder, bucket_id| {
+                builder.call_method(self.component, "sell", args!(bucket_id, RADIX_TOKEN))
+            })
+            .call_method(
+                self.user_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_user(manifest)
+    }
+
+    // Prices the tokens in XRD along a curve of 1 + 0.01 * s with selling back enabled.
+    fn start_sell_back_curve(&mut self) {
+        self.mint_role_badge(Role::PriceManager);
+        let price_manager_badge = self.price_manager_badge;
+        let curve = BondingCurve::Linear {
+            initial_price: dec!("1"),
+            slope: dec!("0.01"),
+        };
+        self.call_with_badge(
+            price_manager_badge,
+            "start_bonding_curve",
+            args!(RADIX_TOKEN, curve, true),
+        )
+        .expect_commit_success();
+    }
+
+    // Calls a method of the sale presenting the given badge of the seller account, depositing
+    // anything returned into the seller account.
+    fn call_with_badge(
+        &mut self,
+        badge: ResourceAddress,
+        method: &str,
+        args: Vec<u8>,
+    ) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .create_proof_from_account(self.seller_account, badge)
+            .call_method(self.component, method, args)
+            .call_method(
+                self.seller_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_seller(manifest)
+    }
+
+    // Mints a badge of the given role with the seller badge, depositing it into the seller account.
+    fn mint_role_badge(&mut self, role: Role) {
+        let manifest = ManifestBuilder::new()
+            .create_proof_from_account(self.seller_account, self.seller_badge)
+            .call_method(self.component, "mint_role_badge", args!(role))
+            .call_method(
+                self.seller_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_seller(manifest).expect_commit_success();
+    }
+
+    // Withdraws the given amount of XRD into the seller account, presenting the given badge of the
+    // seller account if any.
+    fn withdraw_funds(
+        &mut self,
+        badge: Option<ResourceAddress>,
+        amount: Decimal,
+    ) -> TransactionReceipt {
+        let mut manifest_builder = ManifestBuilder::new();
+        if let Some(badge) = badge {
+            manifest_builder.create_proof_from_account(self.seller_account, badge);
+        }
+        let manifest = manifest_builder
+            .call_method(self.component, "withdraw_funds", args!(RADIX_TOKEN, amount))
+            .call_method(
+                self.seller_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_seller(manifest)
+    }
+
+    // Changes the price of the tokens in XRD, presenting the given badge of the seller account if
+    // any.
+    fn change_price(
+        &mut self,
+        badge: Option<ResourceAddress>,
+        price: Decimal,
+    ) -> TransactionReceipt {
+        let mut manifest_builder = ManifestBuilder::new();
+        if let Some(badge) = badge {
+            manifest_builder.create_proof_from_account(self.seller_account, badge);
+        }
+        let manifest = manifest_builder
+            .call_method(self.component, "change_price", args!(RADIX_TOKEN, price))
+            .build();
+        self.execute_as_seller(manifest)
+    }
+}
+
+#[test]
+fn instantiating_creates_the_token_with_its_supply_and_metadata() {
+    let mut sale = TestSale::new();
+
+    let component_resources = sale.test_runner.get_component_resources(sale.component);
+    assert_eq!(component_resources.get(&sale.useful_token), Some(&dec!("1000")));
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, sale.seller_badge),
+        Some(dec!("1"))
+    );
+
+    let metadata = sale.test_runner.get_metadata(GlobalAddress::Resource(sale.useful_token));
+    assert_eq!(metadata.get("name"), Some(&"UsefulToken".to_string()));
+    assert_eq!(metadata.get("symbol"), Some(&"USEFUL".to_string()));
+    assert_eq!(metadata.get("description"), Some(&"A very useful token".to_string()));
+    assert_eq!(
+        metadata.get("icon_url"),
+        Some(&"https://example.com/useful.png".to_string())
+    );
+}
+
+#[test]
+fn buying_gives_the_payment_divided_by_the_price_in_tokens() {
+    let mut sale = TestSale::new();
+    let xrd_balance = sale.test_runner.account_balance(sale.user_account, RADIX_TOKEN).unwrap();
+
+    sale.buy(dec!("50")).expect_commit_success();
+
+    // 50 XRD at 0.5 XRD per token buys 100 tokens, and the payment stays in the component
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("100"))
+    );
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, RADIX_TOKEN),
+        Some(xrd_balance - dec!("50"))
+    );
+    let component_resources = sale.test_runner.get_component_resources(sale.component);
+    assert_eq!(component_resources.get(&sale.useful_token), Some(&dec!("900")));
+    assert_eq!(component_resources.get(&RADIX_TOKEN), Some(&dec!("50")));
+}
+
+#[test]
+fn buying_more_tokens_than_are_left_fails() {
+    let mut sale = TestSale::new();
+
+    // 600 XRD at 0.5 XRD per token would buy 1200 tokens out of the 1000 for sale
+    sale.buy(dec!("600")).expect_commit_failure();
+}
+
+#[test]
+fn withdrawing_funds_without_a_badge_fails() {
+    let mut sale = TestSale::new();
+    sale.buy(dec!("50")).expect_commit_success();
+
+    sale.withdraw_funds(None, dec!("50")).expect_commit_failure();
+}
+
+#[test]
+fn withdrawing_funds_with_the_seller_badge_alone_fails() {
+    let mut sale = TestSale::new();
+    sale.buy(dec!("50")).expect_commit_success();
+
+    // Withdrawals require the treasurer badge, which the seller must mint first
+    let seller_badge = sale.seller_badge;
+    sale.withdraw_funds(Some(seller_badge), dec!("50")).expect_commit_failure();
+}
+
+#[test]
+fn withdrawing_funds_with_the_treasurer_badge_succeeds() {
+    let mut sale = TestSale::new();
+    sale.buy(dec!("50")).expect_commit_success();
+    sale.mint_role_badge(Role::Treasurer);
+    let xrd_balance = sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN).unwrap();
+
+    let treasurer_badge = sale.treasurer_badge;
+    sale.withdraw_funds(Some(treasurer_badge), dec!("50")).expect_commit_success();
+
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN),
+        Some(xrd_balance + dec!("50"))
+    );
+}
+
+#[test]
+fn minting_a_role_badge_without_the_seller_badge_fails() {
+    let mut sale = TestSale::new();
+
+    let manifest = ManifestBuilder::new()
+        .call_method(sale.component, "mint_role_badge", args!(Role::Treasurer))
+        .call_method(
+            sale.user_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_user(manifest).expect_commit_failure();
+}
+
+#[test]
+fn changing_the_price_with_the_seller_badge_alone_fails() {
+    let mut sale = TestSale::new();
+
+    // Changing the price requires the price manager badge, which the seller must mint first
+    let seller_badge = sale.seller_badge;
+    sale.change_price(Some(seller_badge), dec!("1")).expect_commit_failure();
+}
+
+#[test]
+fn changing_the_price_without_a_badge_fails() {
+    let mut sale = TestSale::new();
+
+    sale.change_price(None, dec!("1")).expect_commit_failure();
+}
+
+#[test]
+fn changing_the_price_with_the_price_manager_badge_succeeds() {
+    let mut sale = TestSale::new();
+    sale.mint_role_badge(Role::PriceManager);
+
+    let price_manager_badge = sale.price_manager_badge;
+    sale.change_price(Some(price_manager_badge), dec!("1")).expect_commit_success();
+
+    // 50 XRD now only buys 50 tokens at the new price of 1 XRD per token
+    sale.buy(dec!("50")).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("50"))
+    );
+}
+
+// Instantiates a sale from epoch 10 to epoch 20 which reserves 10% of its tokens and half of its
+// XRD proceeds for a pool whose shares are locked for 5 epochs, and buys 100 tokens for 50 XRD.
+fn sale_seeding_liquidity() -> TestSale {
+    let mut sale = TestSale::with_epochs(10, 20);
+    let seller_badge = sale.seller_badge;
+    sale.call_with_badge(
+        seller_badge,
+        "configure_liquidity_seeding",
+        args!(RADIX_TOKEN, dec!("0.5"), dec!("0.1"), dec!("0"), 5u64),
+    )
+    .expect_commit_success();
+
+    sale.test_runner.set_current_epoch(10);
+    sale.buy(dec!("50")).expect_commit_success();
+    sale
+}
+
+// Finalizes the sale as the user, who holds no badge.
+fn finalize(sale: &mut TestSale) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .call_method(sale.component, "finalize", args!())
+        .build();
+    sale.execute_as_user(manifest)
+}
+
+#[test]
+fn finalizing_seeds_a_pool_with_the_reserved_tokens_and_proceeds() {
+    let mut sale = sale_seeding_liquidity();
+    sale.test_runner.set_current_epoch(21);
+
+    let receipt = finalize(&mut sale);
+    receipt.expect_commit_success();
+    let pool = receipt.expect_commit().entity_changes.new_component_addresses[0];
+
+    // 100 of the 1000 tokens and half of the 50 XRD paid are in the pool
+    let pool_resources = sale.test_runner.get_component_resources(pool);
+    assert_eq!(pool_resources.get(&sale.useful_token), Some(&dec!("100")));
+    assert_eq!(pool_resources.get(&RADIX_TOKEN), Some(&dec!("25")));
+
+    // The sale can only be finalized once
+    finalize(&mut sale).expect_commit_failure();
+}
+
+#[test]
+fn finalizing_before_the_sale_ends_fails() {
+    let mut sale = sale_seeding_liquidity();
+
+    finalize(&mut sale).expect_commit_failure();
+}
+
+#[test]
+fn pool_shares_are_locked_until_their_unlock_epoch() {
+    let mut sale = sale_seeding_liquidity();
+    sale.test_runner.set_current_epoch(21);
+    let receipt = finalize(&mut sale);
+    receipt.expect_commit_success();
+
+    // The resources are created in order: the pool share minter badge and the pool shares
+    let pool_shares = receipt.expect_commit().entity_changes.new_resource_addresses[1];
+    let seller_badge = sale.seller_badge;
+
+    sale.call_with_badge(seller_badge, "withdraw_liquidity_shares", args!())
+        .expect_commit_failure();
+
+    sale.test_runner.set_current_epoch(26);
+    sale.call_with_badge(seller_badge, "withdraw_liquidity_shares", args!())
+        .expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, pool_shares),
+        Some(dec!("100"))
+    );
+}
+
+#[test]
+fn withdrawing_the_pool_shares_without_the_seller_badge_fails() {
+    let mut sale = sale_seeding_liquidity();
+    sale.test_runner.set_current_epoch(21);
+    finalize(&mut sale).expect_commit_success();
+    sale.test_runner.set_current_epoch(26);
+
+    let manifest = ManifestBuilder::new()
+        .call_method(sale.component, "withdraw_liquidity_shares", args!())
+        .call_method(
+            sale.user_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_user(manifest).expect_commit_failure();
+}
+
+#[test]
+fn recovering_the_seller_badge_revokes_the_lost_one() {
+    let mut sale = TestSale::new();
+    let seller_badge = sale.seller_badge;
+
+    let receipt = sale.call_with_badge(seller_badge, "configure_recovery", args!(1u8, 1u8));
+    receipt.expect_commit_success();
+    let guardian_badge = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    let receipt = sale.call_with_badge(guardian_badge, "recover_seller_badge", args!());
+    receipt.expect_commit_success();
+    let new_seller_badge = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    // Only the new seller badge is accepted from now on
+    sale.call_with_badge(seller_badge, "mint_role_badge", args!(Role::Pauser))
+        .expect_commit_failure();
+    sale.call_with_badge(new_seller_badge, "mint_role_badge", args!(Role::Pauser))
+        .expect_commit_success();
+}
+
+#[test]
+fn recovering_the_seller_badge_revokes_the_role_badges() {
+    let mut sale = TestSale::new();
+    let seller_badge = sale.seller_badge;
+    sale.buy(dec!("50")).expect_commit_success();
+    sale.mint_role_badge(Role::Treasurer);
+
+    let receipt = sale.call_with_badge(seller_badge, "configure_recovery", args!(1u8, 1u8));
+    receipt.expect_commit_success();
+    let guardian_badge = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    // The new seller badge is created first, followed by the new price manager, treasurer and
+    // pauser badges
+    let receipt = sale.call_with_badge(guardian_badge, "recover_seller_badge", args!());
+    receipt.expect_commit_success();
+    let new_resources = &receipt.expect_commit().entity_changes.new_resource_addresses;
+    let new_seller_badge = new_resources[0];
+    let new_treasurer_badge = new_resources[2];
+
+    // The treasurer badges minted before the recovery are no longer accepted
+    let treasurer_badge = sale.treasurer_badge;
+    sale.withdraw_funds(Some(treasurer_badge), dec!("50")).expect_commit_failure();
+
+    sale.call_with_badge(new_seller_badge, "mint_role_badge", args!(Role::Treasurer))
+        .expect_commit_success();
+    sale.withdraw_funds(Some(new_treasurer_badge), dec!("50")).expect_commit_success();
+}
+
+#[test]
+fn selling_back_pays_along_the_curve_regardless_of_other_sales() {
+    let mut sale = TestSale::new();
+    let seller_badge = sale.seller_badge;
+    let user_account = sale.user_account;
+    let usdc = sale
+        .test_runner
+        .create_fungible_resource(dec!("10000"), 18, user_account);
+    sale.call_with_badge(seller_badge, "add_payment_resource", args!(usdc, dec!("1")))
+        .expect_commit_success();
+    sale.start_sell_back_curve();
+    let xrd_balance = sale.test_runner.account_balance(user_account, RADIX_TOKEN).unwrap();
+
+    // 10.5 XRD buys the first 10 tokens along the curve, which cost 10 + 0.01 * 10^2 / 2
+    sale.buy(dec!("10.5")).expect_commit_success();
+    let tokens_bought = sale
+        .test_runner
+        .account_balance(user_account, sale.useful_token)
+        .unwrap();
+    assert!(tokens_bought > dec!("9.999999") && tokens_bought <= dec!("10"));
+
+    // Selling 500 more tokens for USDC does not move the XRD curve, so selling the tokens bought
+    // with XRD back only returns what was paid for them
+    sale.buy_with(usdc, dec!("500")).expect_commit_success();
+    sale.sell(tokens_bought).expect_commit_success();
+    let xrd_balance_after = sale.test_runner.account_balance(user_account, RADIX_TOKEN).unwrap();
+    assert!(xrd_balance_after <= xrd_balance);
+    assert!(xrd_balance_after > xrd_balance - dec!("0.000001"));
+}
+
+#[test]
+fn selling_back_remains_possible_once_the_sale_is_closed() {
+    let mut sale = TestSale::new();
+    sale.start_sell_back_curve();
+    sale.buy(dec!("10.5")).expect_commit_success();
+    let tokens_bought = sale
+        .test_runner
+        .account_balance(sale.user_account, sale.useful_token)
+        .unwrap();
+
+    let seller_badge = sale.seller_badge;
+    sale.call_with_badge(seller_badge, "close_sale", args!())
+        .expect_commit_success();
+    sale.sell(tokens_bought).expect_commit_success();
+
+    // The tokens sold back after closing the sale can be withdrawn by the seller, who then holds
+    // the whole supply again
+    sale.call_with_badge(seller_badge, "withdraw_unsold_tokens", args!())
+        .expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, sale.useful_token),
+        Some(dec!("1000"))
+    );
+}
+
+#[test]
+fn payments_are_split_between_the_beneficiaries() {
+    let mut sale = TestSale::new();
+    sale.mint_role_badge(Role::Treasurer);
+    let seller_badge = sale.seller_badge;
+
+    let receipt = sale.call_with_badge(
+        seller_badge,
+        "configure_beneficiaries",
+        args!(vec![dec!("0.2"), dec!("0.3")]),
+    );
+    receipt.expect_commit_success();
+    let beneficiary_badge = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, beneficiary_badge),
+        Some(dec!("2"))
+    );
+
+    sale.buy(dec!("50")).expect_commit_success();
+
+    // The second beneficiary gets 30% of the 50 XRD paid
+    let xrd_balance = sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN).unwrap();
+    let manifest = ManifestBuilder::new()
+        .create_proof_from_account_by_ids(
+            sale.seller_account,
+            &BTreeSet::from([NonFungibleLocalId::Integer(2)]),
+            beneficiary_badge,
+        )
+        .pop_from_auth_zone(|builder, proof_id| {
+            builder.call_method(sale.component, "withdraw_beneficiary_funds", args!(proof_id))
+        })
+        .call_method(
+            sale.seller_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_seller(manifest).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN),
+        Some(xrd_balance + dec!("15"))
+    );
+
+    // Only the 25 XRD left once both shares are taken can be withdrawn by the treasurer
+    let treasurer_badge = sale.treasurer_badge;
+    sale.withdraw_funds(Some(treasurer_badge), dec!("26")).expect_commit_failure();
+    sale.withdraw_funds(Some(treasurer_badge), dec!("25")).expect_commit_success();
+}
+
+#[test]
+fn withdrawing_beneficiary_funds_without_a_beneficiary_badge_fails() {
+    let mut sale = TestSale::new();
+    let seller_badge = sale.seller_badge;
+
+    sale.call_with_badge(
+        seller_badge,
+        "configure_beneficiaries",
+        args!(vec![dec!("0.5")]),
+    )
+    .expect_commit_success();
+    sale.buy(dec!("50")).expect_commit_success();
+
+    // The user presents a proof of the tokens they bought instead of a beneficiary badge
+    let manifest = ManifestBuilder::new()
+        .create_proof_from_account(sale.user_account, sale.useful_token)
+        .pop_from_auth_zone(|builder, proof_id| {
+            builder.call_method(sale.component, "withdraw_beneficiary_funds", args!(proof_id))
+        })
+        .call_method(
+            sale.user_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_user(manifest).expect_commit_failure();
+}
+
+#[test]
+fn recovering_the_seller_badge_without_the_guardians_fails() {
+    let mut sale = TestSale::new();
+    let seller_badge = sale.seller_badge;
+
+    sale.call_with_badge(seller_badge, "configure_recovery", args!(1u8, 1u8))
+        .expect_commit_success();
+    sale.call_with_badge(seller_badge, "recover_seller_badge", args!())
+        .expect_commit_failure();
+}
+
+// Withdraws 50 XRD into the seller account presenting the treasury signer badges with the given
+// ids, which the seller account holds.
+fn withdraw_funds_with_signers(
+    sale: &mut TestSale,
+    treasury_signer_badge: ResourceAddress,
+    signer_ids: &[u64],
+) -> TransactionReceipt {
+    let signer_ids: BTreeSet<NonFungibleLocalId> = signer_ids
+        .iter()
+        .map(|signer_id| NonFungibleLocalId::Integer(*signer_id))
+        .collect();
+    let manifest = ManifestBuilder::new()
+        .create_proof_from_account_by_ids(sale.seller_account, &signer_ids, treasury_signer_badge)
+        .call_method(sale.component, "withdraw_funds", args!(RADIX_TOKEN, dec!("50")))
+        .call_method(
+            sale.seller_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_seller(manifest)
+}
+
+#[test]
+fn multisig_withdrawals_require_the_threshold_of_signers() {
+    let mut sale = TestSale::new();
+    let seller_badge = sale.seller_badge;
+    sale.buy(dec!("50")).expect_commit_success();
+    sale.mint_role_badge(Role::Treasurer);
+
+    let receipt = sale.call_with_badge(
+        seller_badge,
+        "configure_multisig_withdrawals",
+        args!(3u8, 2u8),
+    );
+    receipt.expect_commit_success();
+    let treasury_signer_badge = receipt.expect_commit().entity_changes.new_resource_addresses[0];
+
+    // The treasurer badge is replaced by the treasury signers
+    let treasurer_badge = sale.treasurer_badge;
+    sale.withdraw_funds(Some(treasurer_badge), dec!("50")).expect_commit_failure();
+
+    // A single signer is below the threshold of two
+    withdraw_funds_with_signers(&mut sale, treasury_signer_badge, &[1]).expect_commit_failure();
+
+    let xrd_balance = sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN).unwrap();
+    withdraw_funds_with_signers(&mut sale, treasury_signer_badge, &[1, 3]).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN),
+        Some(xrd_balance + dec!("50"))
+    );
+}
+
+// Instantiates a sale from epoch 10 to epoch 100 whose tokens vest over 20 epochs with a cliff of
+// 5 epochs, and buys 100 tokens for 50 XRD at epoch 10.
+fn sale_with_vesting() -> TestSale {
+    let mut sale = TestSale::with_epochs(10, 100);
+    let seller_badge = sale.seller_badge;
+    sale.call_with_badge(seller_badge, "configure_vesting", args!(5u64, 20u64))
+        .expect_commit_success();
+
+    sale.test_runner.set_current_epoch(10);
+    sale.buy(dec!("50")).expect_commit_success();
+    sale
+}
+
+// Claims the vested tokens of the vesting NFT of the user into the user account.
+fn claim(sale: &mut TestSale) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .create_proof_from_account(sale.user_account, sale.vesting_nft)
+        .pop_from_auth_zone(|builder, proof_id| {
+            builder.call_method(sale.component, "claim", args!(proof_id))
+        })
+        .call_method(
+            sale.user_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_user(manifest)
+}
+
+#[test]
+fn claiming_before_the_cliff_fails() {
+    let mut sale = sale_with_vesting();
+    sale.test_runner.set_current_epoch(14);
+
+    claim(&mut sale).expect_commit_failure();
+}
+
+#[test]
+fn claiming_releases_the_tokens_vested_so_far() {
+    let mut sale = sale_with_vesting();
+
+    // A quarter of the tokens has vested 5 epochs into the 20 epochs of vesting
+    sale.test_runner.set_current_epoch(15);
+    claim(&mut sale).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("25"))
+    );
+
+    // Claiming again in the same epoch releases nothing more
+    claim(&mut sale).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("25"))
+    );
+
+    // Only the tokens vested since the previous claim are released
+    sale.test_runner.set_current_epoch(20);
+    claim(&mut sale).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("50"))
+    );
+}
+
+#[test]
+fn claiming_once_fully_vested_releases_all_the_tokens() {
+    let mut sale = sale_with_vesting();
+
+    sale.test_runner.set_current_epoch(40);
+    claim(&mut sale).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("100"))
+    );
+
+    // Nothing is left to claim afterwards
+    sale.test_runner.set_current_epoch(50);
+    claim(&mut sale).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("100"))
+    );
+}
+
+// Instantiates a sale from epoch 10 to epoch 20 with the given soft cap, and buys 100 tokens for
+// 50 XRD.
+fn sale_with_soft_cap(soft_cap: Decimal) -> TestSale {
+    let mut sale = TestSale::with_epochs(10, 20);
+    let seller_badge = sale.seller_badge;
+    sale.call_with_badge(seller_badge, "configure_soft_cap", args!(soft_cap))
+        .expect_commit_success();
+
+    sale.test_runner.set_current_epoch(10);
+    sale.buy(dec!("50")).expect_commit_success();
+    sale
+}
+
+// Returns the buyer receipt of the user along with the 100 tokens recorded on it for a refund.
+fn refund(sale: &mut TestSale) -> TransactionReceipt {
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account_by_amount(sale.user_account, dec!("1"), sale.buyer_receipt)
+        .withdraw_from_account_by_amount(sale.user_account, dec!("100"), sale.useful_token)
+        .take_from_worktop(sale.buyer_receipt, |builder, receipt_id| {
+            builder.take_from_worktop(sale.useful_token, |builder, tokens_id| {
+                builder.call_method(sale.component, "refund", args!(receipt_id, tokens_id))
+            })
+        })
+        .call_method(
+            sale.user_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_user(manifest)
+}
+
+#[test]
+fn withdrawing_funds_before_a_soft_capped_sale_ends_fails() {
+    let mut sale = sale_with_soft_cap(dec!("50"));
+    sale.mint_role_badge(Role::Treasurer);
+    let treasurer_badge = sale.treasurer_badge;
+
+    // The soft cap is reached, but the proceeds stay locked until the end of the sale
+    sale.test_runner.set_current_epoch(15);
+    sale.withdraw_funds(Some(treasurer_badge), dec!("50")).expect_commit_failure();
+
+    sale.test_runner.set_current_epoch(21);
+    sale.withdraw_funds(Some(treasurer_badge), dec!("50")).expect_commit_success();
+}
+
+#[test]
+fn refunding_below_the_soft_cap_returns_the_payment() {
+    let mut sale = sale_with_soft_cap(dec!("500"));
+    let xrd_balance = sale.test_runner.account_balance(sale.user_account, RADIX_TOKEN).unwrap();
+
+    // Refunds are only possible once the sale has ended
+    refund(&mut sale).expect_commit_failure();
+
+    sale.test_runner.set_current_epoch(21);
+    refund(&mut sale).expect_commit_success();
+
+    // The receipt is burned and the 50 XRD paid are returned in exchange for the tokens
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, RADIX_TOKEN),
+        Some(xrd_balance + dec!("50"))
+    );
+    assert!(sale
+        .test_runner
+        .account_balance(sale.user_account, sale.buyer_receipt)
+        .map_or(true, |balance| balance.is_zero()));
+    let component_resources = sale.test_runner.get_component_resources(sale.component);
+    assert_eq!(component_resources.get(&sale.useful_token), Some(&dec!("1000")));
+}
+
+#[test]
+fn refunding_once_the_soft_cap_is_reached_fails() {
+    let mut sale = sale_with_soft_cap(dec!("100"));
+    sale.test_runner.set_current_epoch(21);
+
+    refund(&mut sale).expect_commit_failure();
+}
+
+// A mock oracle reporting the price of XRD in USD, instantiated by the seller who holds its admin
+// badge.
+struct TestOracle {
+    component: ComponentAddress,
+    admin_badge: ResourceAddress,
+}
+
+// Instantiates a sale priced at 1 USD per token in XRD, along with a mock oracle reporting 4 USD
+// per XRD which goes out of date after 10 epochs without an update.
+fn sale_priced_in_usd(fallback: OracleFallback) -> (TestSale, TestOracle) {
+    let mut sale = TestSale::new();
+    let manifest = ManifestBuilder::new()
+        .call_function(
+            sale.package_address,
+            "MockOracle",
+            "instantiate_mock_oracle",
+            args!(dec!("4")),
+        )
+        .call_method(
+            sale.seller_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    let receipt = sale.execute_as_seller(manifest);
+    receipt.expect_commit_success();
+    let oracle = TestOracle {
+        component: receipt.expect_commit().entity_changes.new_component_addresses[0],
+        admin_badge: receipt.expect_commit().entity_changes.new_resource_addresses[0],
+    };
+
+    sale.mint_role_badge(Role::PriceManager);
+    let price_manager_badge = sale.price_manager_badge;
+    sale.call_with_badge(
+        price_manager_badge,
+        "start_usd_pricing",
+        args!(RADIX_TOKEN, dec!("1"), oracle.component, 10u64, fallback),
+    )
+    .expect_commit_success();
+    (sale, oracle)
+}
+
+// Sets the price reported by the oracle, presenting the given badge of the seller account if any.
+fn set_oracle_price(
+    sale: &mut TestSale,
+    oracle: &TestOracle,
+    badge: Option<ResourceAddress>,
+    price: Decimal,
+) -> TransactionReceipt {
+    let mut manifest_builder = ManifestBuilder::new();
+    if let Some(badge) = badge {
+        manifest_builder.create_proof_from_account(sale.seller_account, badge);
+    }
+    let manifest = manifest_builder
+        .call_method(oracle.component, "set_price", args!(price))
+        .build();
+    sale.execute_as_seller(manifest)
+}
+
+#[test]
+fn buying_at_a_usd_price_converts_it_with_the_oracle_price() {
+    let (mut sale, _oracle) = sale_priced_in_usd(OracleFallback::Halt);
+
+    // 1 USD per token at 4 USD per XRD is 0.25 XRD per token, so 50 XRD buys 200 tokens
+    sale.buy(dec!("50")).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("200"))
+    );
+}
+
+#[test]
+fn buying_with_an_outdated_oracle_fails_when_halting() {
+    let (mut sale, oracle) = sale_priced_in_usd(OracleFallback::Halt);
+    sale.test_runner.set_current_epoch(11);
+
+    sale.buy(dec!("50")).expect_commit_failure();
+
+    // Updating the oracle lets the sale resume at the new price of 1 USD per XRD
+    let admin_badge = oracle.admin_badge;
+    set_oracle_price(&mut sale, &oracle, Some(admin_badge), dec!("1")).expect_commit_success();
+    sale.buy(dec!("50")).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("50"))
+    );
+}
+
+#[test]
+fn buying_with_an_outdated_oracle_uses_the_fixed_fallback_price() {
+    let (mut sale, _oracle) = sale_priced_in_usd(OracleFallback::FixedPrice(dec!("1")));
+    sale.test_runner.set_current_epoch(11);
+
+    // The fallback price of 1 XRD per token applies instead of the 0.25 XRD from the oracle
+    sale.buy(dec!("50")).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("50"))
+    );
+}
+
+#[test]
+fn buying_with_an_outdated_oracle_uses_the_last_oracle_price() {
+    let (mut sale, _oracle) = sale_priced_in_usd(OracleFallback::LastOraclePrice);
+    sale.test_runner.set_current_epoch(11);
+
+    sale.buy(dec!("50")).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("200"))
+    );
+}
+
+#[test]
+fn setting_the_oracle_price_requires_the_admin_badge() {
+    let (mut sale, oracle) = sale_priced_in_usd(OracleFallback::Halt);
+
+    set_oracle_price(&mut sale, &oracle, None, dec!("1")).expect_commit_failure();
+    let seller_badge = sale.seller_badge;
+    set_oracle_price(&mut sale, &oracle, Some(seller_badge), dec!("1")).expect_commit_failure();
+
+    let admin_badge = oracle.admin_badge;
+    set_oracle_price(&mut sale, &oracle, Some(admin_badge), dec!("1")).expect_commit_success();
+    sale.buy(dec!("50")).expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.useful_token),
+        Some(dec!("50"))
+    );
+}
+
+// A Radiswap pool of 100 tokens and 100 XRD instantiated on a local ledger simulator, along with
+// the account which provided its liquidity and holds its pool shares.
+struct TestPool {
+    test_runner: TestRunner,
+    public_key: EcdsaSecp256k1PublicKey,
+    account: ComponentAddress,
+    component: ComponentAddress,
+    token: ResourceAddress,
+    pool_shares: ResourceAddress,
+}
+
+impl TestPool {
+    // Instantiates a pool with the given fee out of a new token with a supply of 1000.
+    fn new(fee: Decimal) -> Self {
+        let mut test_runner = TestRunner::new(true);
+        let (public_key, _private_key, account) = test_runner.new_allocated_account();
+        let package_address = test_runner.compile_and_publish(this_package!());
+        let token = test_runner.create_fungible_resource(dec!("1000"), 18, account);
+
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account_by_amount(account, dec!("100"), token)
+            .withdraw_from_account_by_amount(account, dec!("100"), RADIX_TOKEN)
+            .take_from_worktop(token, |builder, token_bucket| {
+                builder.take_from_worktop(RADIX_TOKEN, |builder, xrd_bucket| {
+                    builder.call_function(
+                        package_address,
+                        "Radiswap",
+                        "instantiate_pool",
+                        args!(token_bucket, xrd_bucket, dec!("100"), fee),
+                    )
+                })
+            })
+            .call_method(
+                account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt.expect_commit_success();
+
+        // The resources are created in order: the pool share minter badge and the pool shares.
+        Self {
+            component: receipt.expect_commit().entity_changes.new_component_addresses[0],
+            pool_shares: receipt.expect_commit().entity_changes.new_resource_addresses[1],
+            test_runner,
+            public_key,
+            account,
+            token,
+        }
+    }
+
+    fn execute(&mut self, manifest: TransactionManifest) -> TransactionReceipt {
+        let receipt = self.test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&self.public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt
+    }
+
+    fn balance(&mut self, resource_address: ResourceAddress) -> Decimal {
+        self.test_runner
+            .account_balance(self.account, resource_address)
+            .unwrap_or(Decimal::zero())
+    }
+
+    // Swaps the given amount of the given resource through the pool.
+    fn swap(&mut self, resource_address: ResourceAddress, amount: Decimal) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account_by_amount(self.account, amount, resource_address)
+            .take_from_worktop(resource_address, |builder, bucket_id| {
+                builder.call_method(self.component, "swap", args!(bucket_id))
+            })
+            .call_method(
+                self.account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute(manifest)
+    }
+}
+
+#[test]
+fn instantiating_a_pool_gives_its_pool_shares_to_the_provider() {
+    let mut pool = TestPool::new(dec!("0"));
+
+    let pool_shares = pool.pool_shares;
+    assert_eq!(pool.balance(pool_shares), dec!("100"));
+    let token = pool.token;
+    let component_resources = pool.test_runner.get_component_resources(pool.component);
+    assert_eq!(component_resources.get(&token), Some(&dec!("100")));
+    assert_eq!(component_resources.get(&RADIX_TOKEN), Some(&dec!("100")));
+}
+
+#[test]
+fn swapping_keeps_the_product_of_the_reserves() {
+    let mut pool = TestPool::new(dec!("0"));
+    let token = pool.token;
+
+    // Swapping 100 XRD into reserves of 100 tokens and 100 XRD gives 100 * 100 / 200 = 50 tokens
+    pool.swap(RADIX_TOKEN, dec!("100")).expect_commit_success();
+    assert_eq!(pool.balance(token), dec!("950"));
+}
+
+#[test]
+fn swapping_takes_the_fee_out_of_the_input() {
+    let mut pool = TestPool::new(dec!("0.01"));
+    let token = pool.token;
+
+    // Only 99 of the 100 XRD are swapped, giving 99 * 100 / 199 tokens
+    pool.swap(RADIX_TOKEN, dec!("100")).expect_commit_success();
+    assert_eq!(pool.balance(token), dec!("949.748743718592964824"));
+}
+
+#[test]
+fn swapping_a_token_which_is_not_in_the_pool_fails() {
+    let mut pool = TestPool::new(dec!("0"));
+    let account = pool.account;
+    let other_token = pool
+        .test_runner
+        .create_fungible_resource(dec!("1000"), 18, account);
+
+    pool.swap(other_token, dec!("10")).expect_commit_failure();
+}
+
+#[test]
+fn adding_liquidity_keeps_the_ratio_of_the_reserves() {
+    let mut pool = TestPool::new(dec!("0"));
+    let (token, pool_shares) = (pool.token, pool.pool_shares);
+    let xrd_balance = pool.balance(RADIX_TOKEN);
+
+    // Only 10 of the 20 XRD match the 10 tokens added, so the other 10 XRD are returned
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account_by_amount(pool.account, dec!("10"), token)
+        .withdraw_from_account_by_amount(pool.account, dec!("20"), RADIX_TOKEN)
+        .take_from_worktop(token, |builder, token_bucket| {
+            builder.take_from_worktop(RADIX_TOKEN, |builder, xrd_bucket| {
+                builder.call_method(
+                    pool.component,
+                    "add_liquidity",
+                    args!(token_bucket, xrd_bucket),
+                )
+            })
+        })
+        .call_method(
+            pool.account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    pool.execute(manifest).expect_commit_success();
+
+    assert_eq!(pool.balance(token), dec!("890"));
+    assert_eq!(pool.balance(RADIX_TOKEN), xrd_balance - dec!("10"));
+    assert_eq!(pool.balance(pool_shares), dec!("110"));
+}
+
+#[test]
+fn removing_liquidity_returns_the_share_of_the_reserves() {
+    let mut pool = TestPool::new(dec!("0"));
+    let (token, pool_shares) = (pool.token, pool.pool_shares);
+    let xrd_balance = pool.balance(RADIX_TOKEN);
+
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account_by_amount(pool.account, dec!("50"), pool_shares)
+        .take_from_worktop(pool_shares, |builder, bucket_id| {
+            builder.call_method(pool.component, "remove_liquidity", args!(bucket_id))
+        })
+        .call_method(
+            pool.account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    pool.execute(manifest).expect_commit_success();
+
+    assert_eq!(pool.balance(token), dec!("950"));
+    assert_eq!(pool.balance(RADIX_TOKEN), xrd_balance + dec!("50"));
+    assert_eq!(pool.balance(pool_shares), dec!("50"));
+}
+
+// An NFT sale of a new collection of three items at 10 XRD per item instantiated on a local ledger
+// simulator, along with the account of its seller, who holds the seller badge, and the account of a
+// user without any badge.
+struct TestNftSale {
+    test_runner: TestRunner,
+    seller_public_key: EcdsaSecp256k1PublicKey,
+    seller_account: ComponentAddress,
+    user_public_key: EcdsaSecp256k1PublicKey,
+    user_account: ComponentAddress,
+    component: ComponentAddress,
+    collection: ResourceAddress,
+    seller_badge: ResourceAddress,
+}
+
+impl TestNftSale {
+    fn new() -> Self {
+        let mut test_runner = TestRunner::new(true);
+        let (seller_public_key, _private_key, seller_account) = test_runner.new_allocated_account();
+        let (user_public_key, _private_key, user_account) = test_runner.new_allocated_account();
+        let package_address = test_runner.compile_and_publish(this_package!());
+
+        let items: Vec<(String, String)> = (1..=3)
+            .map(|number| {
+                (
+                    format!("Item #{}", number),
+                    format!("https://example.com/item-{}.png", number),
+                )
+            })
+            .collect();
+        let manifest = ManifestBuilder::new()
+            .call_function(
+                package_address,
+                "NftSale",
+                "instantiate_collection_sale",
+                args!(
+                    "Useful Collection".to_string(),
+                    "A very useful collection".to_string(),
+                    items,
+                    RADIX_TOKEN,
+                    dec!("10")
+                ),
+            )
+            .call_method(
+                seller_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        let receipt = test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&seller_public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt.expect_commit_success();
+
+        // The resources are created in order: the collection minter badge, the collection and the
+        // seller badge.
+        let new_resources = &receipt.expect_commit().entity_changes.new_resource_addresses;
+        Self {
+            component: receipt.expect_commit().entity_changes.new_component_addresses[0],
+            collection: new_resources[1],
+            seller_badge: new_resources[2],
+            test_runner,
+            seller_public_key,
+            seller_account,
+            user_public_key,
+            user_account,
+        }
+    }
+
+    fn execute_as_seller(&mut self, manifest: TransactionManifest) -> TransactionReceipt {
+        let receipt = self.test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&self.seller_public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt
+    }
+
+    fn execute_as_user(&mut self, manifest: TransactionManifest) -> TransactionReceipt {
+        let receipt = self.test_runner.execute_manifest_ignoring_fee(
+            manifest,
+            vec![NonFungibleGlobalId::from_public_key(&self.user_public_key)],
+        );
+        println!("{:?}\n", receipt);
+        receipt
+    }
+
+    // Buys the item with the given number, or a random item costing at most the payment if no
+    // number is given, with the given amount of XRD from the user account.
+    fn buy(&mut self, item_number: Option<u64>, amount: Decimal) -> TransactionReceipt {
+        let manifest = ManifestBuilder::new()
+            .withdraw_from_account_by_amount(self.user_account, amount, RADIX_TOKEN)
+            .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| match item_number {
+                Some(item_number) => builder.call_method(
+                    self.component,
+                    "buy_item",
+                    args!(NonFungibleLocalId::Integer(item_number), bucket_id),
+                ),
+                None => builder.call_method(
+                    self.component,
+                    "buy_random_item",
+                    args!(bucket_id, amount),
+                ),
+            })
+            .call_method(
+                self.user_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_user(manifest)
+    }
+
+    // Calls a method of the sale from the seller account, presenting the seller badge if
+    // `with_badge` is set and depositing anything returned into the seller account.
+    fn call_as_seller(&mut self, with_badge: bool, method: &str, args: Vec<u8>) -> TransactionReceipt {
+        let mut manifest_builder = ManifestBuilder::new();
+        if with_badge {
+            manifest_builder.create_proof_from_account(self.seller_account, self.seller_badge);
+        }
+        let manifest = manifest_builder
+            .call_method(self.component, method, args)
+            .call_method(
+                self.seller_account,
+                "deposit_batch",
+                args!(ManifestExpression::EntireWorktop),
+            )
+            .build();
+        self.execute_as_seller(manifest)
+    }
+}
+
+#[test]
+fn buying_an_item_gives_it_along_with_the_change() {
+    let mut sale = TestNftSale::new();
+    let xrd_balance = sale.test_runner.account_balance(sale.user_account, RADIX_TOKEN).unwrap();
+
+    sale.buy(Some(2), dec!("15")).expect_commit_success();
+
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.collection),
+        Some(dec!("1"))
+    );
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, RADIX_TOKEN),
+        Some(xrd_balance - dec!("10"))
+    );
+    let component_resources = sale.test_runner.get_component_resources(sale.component);
+    assert_eq!(component_resources.get(&sale.collection), Some(&dec!("2")));
+    assert_eq!(component_resources.get(&RADIX_TOKEN), Some(&dec!("10")));
+
+    // The item is no longer for sale
+    sale.buy(Some(2), dec!("10")).expect_commit_failure();
+}
+
+#[test]
+fn buying_an_item_with_an_insufficient_payment_fails() {
+    let mut sale = TestNftSale::new();
+
+    sale.buy(Some(1), dec!("9")).expect_commit_failure();
+}
+
+#[test]
+fn buying_an_item_at_its_own_price() {
+    let mut sale = TestNftSale::new();
+    sale.call_as_seller(
+        true,
+        "set_item_price",
+        args!(NonFungibleLocalId::Integer(3), dec!("25")),
+    )
+    .expect_commit_success();
+
+    sale.buy(Some(3), dec!("10")).expect_commit_failure();
+    sale.buy(Some(3), dec!("25")).expect_commit_success();
+    sale.buy(Some(1), dec!("10")).expect_commit_success();
+}
+
+#[test]
+fn buying_random_items_sells_every_item_once() {
+    let mut sale = TestNftSale::new();
+
+    for _ in 0..3 {
+        sale.buy(None, dec!("10")).expect_commit_success();
+    }
+    assert_eq!(
+        sale.test_runner.account_balance(sale.user_account, sale.collection),
+        Some(dec!("3"))
+    );
+
+    // All of the items have been sold
+    sale.buy(None, dec!("10")).expect_commit_failure();
+}
+
+#[test]
+fn buying_a_random_item_above_the_maximum_price_fails() {
+    let mut sale = TestNftSale::new();
+    for item_number in 1..=3u64 {
+        sale.call_as_seller(
+            true,
+            "set_item_price",
+            args!(NonFungibleLocalId::Integer(item_number), dec!("25")),
+        )
+        .expect_commit_success();
+    }
+
+    // Whichever item is picked costs 25 XRD, more than the maximum of 20 XRD despite the payment
+    // covering it
+    let manifest = ManifestBuilder::new()
+        .withdraw_from_account_by_amount(sale.user_account, dec!("50"), RADIX_TOKEN)
+        .take_from_worktop(RADIX_TOKEN, |builder, bucket_id| {
+            builder.call_method(sale.component, "buy_random_item", args!(bucket_id, dec!("20")))
+        })
+        .call_method(
+            sale.user_account,
+            "deposit_batch",
+            args!(ManifestExpression::EntireWorktop),
+        )
+        .build();
+    sale.execute_as_user(manifest).expect_commit_failure();
+
+    sale.buy(None, dec!("25")).expect_commit_success();
+}
+
+#[test]
+fn withdrawing_the_funds_of_an_nft_sale_requires_the_seller_badge() {
+    let mut sale = TestNftSale::new();
+    sale.buy(Some(1), dec!("10")).expect_commit_success();
+    let xrd_balance = sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN).unwrap();
+
+    sale.call_as_seller(false, "withdraw_funds", args!(dec!("10")))
+        .expect_commit_failure();
+    sale.call_as_seller(true, "withdraw_funds", args!(dec!("10")))
+        .expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, RADIX_TOKEN),
+        Some(xrd_balance + dec!("10"))
+    );
+}
+
+#[test]
+fn withdrawing_the_unsold_items_requires_the_seller_badge() {
+    let mut sale = TestNftSale::new();
+    sale.buy(Some(1), dec!("10")).expect_commit_success();
+
+    sale.call_as_seller(false, "withdraw_unsold_items", args!())
+        .expect_commit_failure();
+    sale.call_as_seller(true, "withdraw_unsold_items", args!())
+        .expect_commit_success();
+    assert_eq!(
+        sale.test_runner.account_balance(sale.seller_account, sale.collection),
+        Some(dec!("2"))
+    );
+}